@@ -21,7 +21,7 @@ pub fn part2(input: &str) -> i64 {
 }
 
 pub fn main(bench: bool) {
-    let input = std::fs::read_to_string(INPUT_FILE).unwrap();
+    let input = utils::input::read(INPUT_FILE);
 
     let iters = 10;
 
@@ -61,12 +61,12 @@ fn test_part1_example() {
 
 // #[test]
 // fn test_part1_facit() {
-//     let input = std::fs::read_to_string(INPUT_FILE).unwrap();
+//     let input = utils::input::read(INPUT_FILE);
 //     assert_eq!(part1(&input), 0);
 // }
 
 // #[test]
 // fn test_part2_facit() {
-//     let input = std::fs::read_to_string(INPUT_FILE).unwrap();
+//     let input = utils::input::read(INPUT_FILE);
 //     assert_eq!(part2(&input), 0);
 // }
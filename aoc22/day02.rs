@@ -60,7 +60,7 @@ pub fn part2(input: &str) -> i64 {
 }
 
 pub fn main(bench: bool) {
-    let input = std::fs::read_to_string(INPUT_FILE).unwrap();
+    let input = utils::input::read(INPUT_FILE);
 
     let iters = 10;
 
@@ -100,12 +100,12 @@ fn test_part2_example() {
 
 #[test]
 fn test_part1_facit() {
-    let input = std::fs::read_to_string(INPUT_FILE).unwrap();
+    let input = utils::input::read(INPUT_FILE);
     assert_eq!(part1(&input), 11906);
 }
 
 #[test]
 fn test_part2_facit() {
-    let input = std::fs::read_to_string(INPUT_FILE).unwrap();
+    let input = utils::input::read(INPUT_FILE);
     assert_eq!(part2(&input), 11186);
 }
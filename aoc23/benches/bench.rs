@@ -11,7 +11,7 @@ fn criterion_benchmark(c: &mut Criterion) {
 }
 fn bench_day01(c: &mut Criterion) {
     let mut group = c.benchmark_group("day01");
-    let input = std::fs::read_to_string("input/day01").unwrap();
+    let input = utils::input::read("input/day01");
     group.bench_function("part1", |b| b.iter(|| day01::part1(&input)));
     group.bench_function("part2 (regex)", |b| b.iter(|| day01::part2_regex(&input)));
     group.bench_function("part2 (no regex)", |b| {
@@ -30,14 +30,14 @@ fn bench_day01(c: &mut Criterion) {
 
 fn bench_day02(c: &mut Criterion) {
     let mut group = c.benchmark_group("day02");
-    let input = std::fs::read_to_string("input/day02").unwrap();
+    let input = utils::input::read("input/day02");
     group.bench_function("part1", |b| b.iter(|| day02::part1(&input)));
     group.bench_function("part2", |b| b.iter(|| day02::part2(&input)));
 }
 
 fn bench_day03(c: &mut Criterion) {
     let mut group = c.benchmark_group("day03");
-    let input = std::fs::read_to_string("input/day03").unwrap();
+    let input = utils::input::read("input/day03");
     group.bench_function("part1", |b| b.iter(|| day03::part1(&input)));
     group.bench_function("part2", |b| b.iter(|| day03::part2(&input)));
     group.bench_function("part1 (btree)", |b| b.iter(|| day03::part1_btree(&input)));
@@ -51,7 +51,7 @@ fn bench_day03(c: &mut Criterion) {
 
 fn bench_day04(c: &mut Criterion) {
     let mut group = c.benchmark_group("day04");
-    let input = std::fs::read_to_string("input/day04").unwrap();
+    let input = utils::input::read("input/day04");
     group.bench_function("part1", |b| b.iter(|| day04::part1(&input)));
     group.bench_function("part2", |b| b.iter(|| day04::part2(&input)));
     group.bench_function("part1 (hash set)", |b| {
@@ -62,14 +62,14 @@ fn bench_day04(c: &mut Criterion) {
 
 fn bench_day05(c: &mut Criterion) {
     let mut group = c.benchmark_group("day05");
-    let input = std::fs::read_to_string("input/day05").unwrap();
+    let input = utils::input::read("input/day05");
     group.bench_function("part1", |b| b.iter(|| day05::part1(&input)));
     group.bench_function("part2", |b| b.iter(|| day05::part2(&input)));
 }
 
 fn bench_day06(c: &mut Criterion) {
     let mut group = c.benchmark_group("day06");
-    let input = std::fs::read_to_string("input/day06").unwrap();
+    let input = utils::input::read("input/day06");
     group.bench_function("part1", |b| b.iter(|| day06::part1(&input)));
     group.bench_function("part2", |b| b.iter(|| day06::part2(&input)));
     group.bench_function("part2 (math)", |b| b.iter(|| day06::part2_math(&input)));
@@ -2,7 +2,7 @@ use aoc23::day03::*;
 use criterion::{criterion_group, criterion_main, Criterion};
 
 fn criterion_benchmark(c: &mut Criterion) {
-    let input = std::fs::read_to_string("input/day03").unwrap();
+    let input = utils::input::read("input/day03");
     c.bench_function("part1", |b| b.iter(|| part1(&input)));
     c.bench_function("part2", |b| b.iter(|| part2(&input)));
     c.bench_function("part1 (btree)", |b| b.iter(|| part1_btree(&input)));
@@ -1,3 +1,6 @@
+use rand::{rngs::StdRng, seq::SliceRandom, Rng};
+use utils::gen;
+
 /// Version 1.
 ///
 /// This one's pretty trivial, nothing fun to optimize.
@@ -249,8 +252,35 @@ fn get_digit_nz(at: &[u8]) -> Option<i64> {
     }
 }
 
+/// Random calibration lines of letters, digits and spelled-out digits, with at least one plain
+/// digit per line. `size` is the number of lines.
+pub fn generate(size: usize, rng: &mut StdRng) -> String {
+    const WORDS: [&str; 9] = [
+        "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
+    ];
+    let mut lines = vec![];
+    for _ in 0..size {
+        let chunks = rng.gen_range(1..=8);
+        let digit_at = rng.gen_range(0..chunks);
+        let mut line = String::new();
+        for ix in 0..chunks {
+            match rng.gen_range(0..3) {
+                _ if ix == digit_at => line.push(char::from(b'0' + rng.gen_range(1..=9))),
+                0 => line.push(char::from(b'0' + rng.gen_range(1..=9))),
+                1 => line.push_str(WORDS.choose(rng).unwrap()),
+                _ => line.push_str(&{
+                    let len = rng.gen_range(1..=5);
+                    gen::word(rng, len, gen::LOWERCASE)
+                }),
+            }
+        }
+        lines.push(line);
+    }
+    lines.join("\n")
+}
+
 pub fn main(bench: bool) {
-    let input = std::fs::read_to_string("./input/day01").unwrap();
+    let input = utils::input::read("./input/day01");
     let iters = 1000;
 
     let fns: Vec<(&'static str, fn(&str) -> i64)> = vec![
@@ -295,13 +325,13 @@ pub fn main(bench: bool) {
 
 #[test]
 fn test_facit_part1() {
-    let input = std::fs::read_to_string("./input/day01").unwrap();
+    let input = utils::input::read("./input/day01");
     assert_eq!(part1(&input), 55712);
 }
 
 #[test]
 fn test_facit_part2() {
-    let input = std::fs::read_to_string("./input/day01").unwrap();
+    let input = utils::input::read("./input/day01");
     assert_eq!(part2_regex(&input), 55413);
     assert_eq!(part2_no_regex(&input), 55413);
     assert_eq!(part2_no_regex_bidir(&input), 55413);
@@ -346,3 +376,40 @@ fn example_part2_overlapping() {
     assert_eq!(part2_no_regex_bidir_add_directly(input), 82);
     assert_eq!(part2_no_regex_bidir_add_directly_byte_lines(input), 82);
 }
+
+#[cfg(test)]
+fn arb_document() -> impl proptest::strategy::Strategy<Value = String> {
+    use proptest::prelude::*;
+    // whole and partial digit words, so they get to overlap and almost-match
+    let tokens = [
+        "one", "two", "three", "four", "five", "six", "seven", "eight", "nine", "1", "2", "3", "4",
+        "5", "6", "7", "8", "9", "on", "tw", "thr", "eigh", "nin", "e", "n", "o", "t", "x",
+    ];
+    let line = prop::collection::vec(prop::sample::select(tokens.to_vec()), 0..8)
+        .prop_map(|tokens| tokens.concat());
+    prop::collection::vec(line, 1..5).prop_map(|lines| lines.join("\n"))
+}
+
+#[cfg(test)]
+proptest::proptest! {
+    #[test]
+    fn test_part2_variants_agree(input in arb_document()) {
+        utils::assert_variants_agree(
+            &input,
+            &[
+                ("part2_regex", part2_regex),
+                ("part2_no_regex", part2_no_regex),
+                ("part2_no_regex_bidir", part2_no_regex_bidir),
+                ("part2_no_regex_bidir_add_directly", part2_no_regex_bidir_add_directly),
+                (
+                    "part2_no_regex_bidir_add_directly_byte_lines",
+                    part2_no_regex_bidir_add_directly_byte_lines,
+                ),
+                (
+                    "part2_no_regex_bidir_add_directly_byte_lines_nz",
+                    part2_no_regex_bidir_add_directly_byte_lines_nz,
+                ),
+            ],
+        );
+    }
+}
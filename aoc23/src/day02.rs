@@ -1,3 +1,6 @@
+use itertools::Itertools;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng};
+
 pub fn part1(input: &str) -> i64 {
     let mut result = 0;
     'line: for line in input.lines() {
@@ -65,8 +68,28 @@ pub fn part2(input: &str) -> i64 {
     result
 }
 
+/// Random games of one to six rounds each. `size` is the number of games.
+pub fn generate(size: usize, rng: &mut StdRng) -> String {
+    let mut lines = vec![];
+    for id in 1..=size {
+        let rounds = (0..rng.gen_range(1..=6))
+            .map(|_| {
+                let mut colors = vec!["red", "green", "blue"];
+                colors.shuffle(rng);
+                colors.truncate(rng.gen_range(1..=3));
+                colors
+                    .into_iter()
+                    .map(|c| format!("{} {c}", rng.gen_range(1..=20)))
+                    .join(", ")
+            })
+            .join("; ");
+        lines.push(format!("Game {id}: {rounds}"));
+    }
+    lines.join("\n")
+}
+
 pub fn main(bench: bool) {
-    let input = std::fs::read_to_string("input/day02").unwrap();
+    let input = utils::input::read("input/day02");
     let iters = 1000;
 
     let fns: [(&'static str, fn(&str) -> i64); 2] = [("part1", part1), ("part2", part2)];
@@ -106,7 +129,7 @@ fn test_part1_example() {
 }
 #[test]
 fn test_part1_facit() {
-    let input = std::fs::read_to_string("input/day02").unwrap();
+    let input = utils::input::read("input/day02");
     assert_eq!(part1(&input), 2317);
 }
 
@@ -123,6 +146,6 @@ fn test_part2_example() {
 }
 #[test]
 fn test_part2_facit() {
-    let input = std::fs::read_to_string("input/day02").unwrap();
+    let input = utils::input::read("input/day02");
     assert_eq!(part2(&input), 74804);
 }
@@ -4,6 +4,8 @@ use std::{
 };
 
 use itertools::Itertools;
+use rand::{rngs::StdRng, Rng};
+use utils::gen;
 
 type Parser = fn(&str) -> (Vec<PartNumber>, Vec<(char, i32, i32)>);
 static mut PARSER: Parser = parse_no_regex;
@@ -110,12 +112,13 @@ pub fn part1_hash(input: &str) -> i32 {
         .into_iter()
         .flatten();
 
-        for symbols in target_rows {
+        // a part next to symbols on more than one row still only counts once
+        'rows: for symbols in target_rows {
             for symbol in symbols {
                 let within_span = *symbol >= part.span.start - 1 && *symbol <= part.span.end;
                 if within_span {
                     result += part.num;
-                    break;
+                    break 'rows;
                 }
             }
         }
@@ -147,12 +150,13 @@ pub fn part1_array(input: &str) -> i32 {
         ]
         .into_iter();
 
-        for symbols in target_rows {
+        // a part next to symbols on more than one row still only counts once
+        'rows: for symbols in target_rows {
             for symbol in symbols {
                 let within_span = *symbol >= part.span.start - 1 && *symbol <= part.span.end;
                 if within_span {
                     result += part.num;
-                    break;
+                    break 'rows;
                 }
             }
         }
@@ -360,8 +364,36 @@ fn parse_linewise_no_regex(input: &str) -> (Vec<PartNumber>, Vec<(char, i32, i32
     (parts, symbols)
 }
 
+/// A random `size` by `size` schematic of part numbers and symbols. Note that `part1_array` only
+/// has room for 148 rows.
+pub fn generate(size: usize, rng: &mut StdRng) -> String {
+    const SYMBOLS: &[u8] = b"**#+$/@=%&-";
+    let mut grid = vec![vec!['.'; size]; size];
+    for row in grid.iter_mut() {
+        let mut col = 0;
+        while col < size {
+            match rng.gen_range(0..10) {
+                0..=1 => {
+                    let num = rng.gen_range(1..1000).to_string();
+                    for (ix, c) in num.chars().enumerate().take(size - col) {
+                        row[col + ix] = c;
+                    }
+                    // leave at least one tile between numbers
+                    col += num.len() + 1;
+                }
+                2 => {
+                    row[col] = SYMBOLS[rng.gen_range(0..SYMBOLS.len())] as char;
+                    col += 1;
+                }
+                _ => col += 1,
+            }
+        }
+    }
+    gen::grid_to_string(&grid)
+}
+
 pub fn run_main(bench: bool) {
-    let input = std::fs::read_to_string("input/day03").unwrap();
+    let input = utils::input::read("input/day03");
 
     let iters = 1000;
 
@@ -433,7 +465,7 @@ fn test_part2_example() {
 
 #[test]
 fn test_part1_facit() {
-    let input = std::fs::read_to_string("input/day03").unwrap();
+    let input = utils::input::read("input/day03");
     assert_eq!(part1(&input), 536576);
     assert_eq!(part1_btree(&input), 536576);
     assert_eq!(part1_hash(&input), 536576);
@@ -441,7 +473,7 @@ fn test_part1_facit() {
 
 #[test]
 fn test_part2_facit() {
-    let input = std::fs::read_to_string("input/day03").unwrap();
+    let input = utils::input::read("input/day03");
     assert_eq!(part2(&input), 75741499);
     assert_eq!(part2_btree(&input), 75741499);
     assert_eq!(part2_hash(&input), 75741499);
@@ -527,3 +559,59 @@ fn test_parse() {
     assert_eq!(parsed_parts, expected_parts);
     assert_eq!(parsed_symbols, expected_symbols);
 }
+
+#[cfg(test)]
+fn arb_schematic() -> impl proptest::strategy::Strategy<Value = String> {
+    use proptest::prelude::*;
+    let tile = prop_oneof![
+        4 => Just('.'),
+        3 => proptest::char::range('0', '9'),
+        1 => prop::sample::select(vec!['*', '#', '+', '$']),
+    ];
+    prop::collection::vec(prop::collection::vec(tile, 8), 1..8).prop_map(|rows| {
+        rows.into_iter()
+            .map(|mut row| {
+                // part numbers are at most three digits, so gear ratios fit in an i32
+                let mut run = 0;
+                for c in row.iter_mut() {
+                    run = if c.is_ascii_digit() { run + 1 } else { 0 };
+                    if run > 3 {
+                        *c = '.';
+                        run = 0;
+                    }
+                }
+                String::from_iter(row)
+            })
+            .join("\n")
+    })
+}
+
+#[cfg(test)]
+proptest::proptest! {
+    #[test]
+    fn test_parsers_agree(input in arb_schematic()) {
+        assert_eq!(parse_regex(&input), parse_no_regex(&input));
+        assert_eq!(parse_regex(&input), parse_linewise_no_regex(&input));
+    }
+
+    #[test]
+    fn test_part1_variants_agree(input in arb_schematic()) {
+        utils::assert_variants_agree(
+            &input,
+            &[
+                ("part1", part1),
+                ("part1_btree", part1_btree),
+                ("part1_hash", part1_hash),
+                ("part1_array", part1_array),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_part2_variants_agree(input in arb_schematic()) {
+        utils::assert_variants_agree(
+            &input,
+            &[("part2", part2), ("part2_btree", part2_btree), ("part2_hash", part2_hash)],
+        );
+    }
+}
@@ -1,5 +1,7 @@
 use std::collections::{BTreeMap, HashSet};
 
+use itertools::Itertools;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng};
 use rustc_hash::FxHashSet;
 
 pub fn part1(input: &str) -> i64 {
@@ -142,8 +144,43 @@ pub fn part2_btree(input: &str) -> i64 {
     result
 }
 
+/// Random scratchcards with ten winning numbers and 25 numbers you have. Cards never win copies
+/// past the end of the table, and wins are cut short before any card passes a billion copies.
+/// `size` is the number of cards.
+pub fn generate(size: usize, rng: &mut StdRng) -> String {
+    let mut copies = vec![1i64; size];
+    let mut lines = vec![];
+    for ix in 0..size {
+        let mut pool = (1..=99).collect::<Vec<i64>>();
+        pool.shuffle(rng);
+        let winning = &pool[0..10];
+        let max_wins = 10.min(size - 1 - ix);
+        let mut wins = (0..3).map(|_| rng.gen_range(0..=max_wins)).min().unwrap();
+        while copies[ix + 1..=ix + wins]
+            .iter()
+            .any(|c| c + copies[ix] > 1_000_000_000)
+        {
+            wins -= 1;
+        }
+        let won = copies[ix];
+        for c in copies[ix + 1..=ix + wins].iter_mut() {
+            *c += won;
+        }
+        let mut have = winning[0..wins].to_vec();
+        have.extend_from_slice(&pool[10..(10 + 25 - wins)]);
+        have.shuffle(rng);
+        lines.push(format!(
+            "Card {:>3}: {} | {}",
+            ix + 1,
+            winning.iter().map(|v| format!("{v:>2}")).join(" "),
+            have.iter().map(|v| format!("{v:>2}")).join(" "),
+        ));
+    }
+    lines.join("\n")
+}
+
 pub fn main(bench: bool) {
-    let input = std::fs::read_to_string("input/day04").unwrap();
+    let input = utils::input::read("input/day04");
 
     let iters = 100;
 
@@ -189,7 +226,7 @@ Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11"#;
 
 #[test]
 fn test_part1_facit() {
-    let input = std::fs::read_to_string("input/day04").unwrap();
+    let input = utils::input::read("input/day04");
     assert_eq!(part1(&input), 27059);
 }
 
@@ -206,7 +243,7 @@ Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11"#;
 
 #[test]
 fn test_part2_facit() {
-    let input = std::fs::read_to_string("input/day04").unwrap();
+    let input = utils::input::read("input/day04");
     assert_eq!(part2(&input), 5744979);
     assert_eq!(part2_hash_set(&input), 5744979);
     assert_eq!(part2_btree(&input), 5744979);
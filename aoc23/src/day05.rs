@@ -1,11 +1,12 @@
 use std::{cmp::Reverse, collections::HashMap, ops::RangeInclusive};
-use utils::range_intersect;
+use utils::range_set::RangeSet;
 
 use indicatif::{
     MultiProgress, ParallelProgressIterator, ProgressBar, ProgressDrawTarget, ProgressIterator,
     ProgressStyle,
 };
 use itertools::Itertools;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng};
 use rayon::iter::{IntoParallelIterator, ParallelIterator};
 
 pub fn part1(input: &str) -> i64 {
@@ -169,7 +170,7 @@ pub fn part2(input: &str) -> i64 {
             let num = v.next()?;
             Some(start..=(start + num - 1))
         })
-        .collect::<Vec<RangeInclusive<i64>>>();
+        .collect::<RangeSet<i64>>();
 
     let mut maps = HashMap::<(&str, &str), Vec<(i64, RangeInclusive<i64>)>>::new();
     let mut current_map: Option<((&str, &str), Vec<(i64, RangeInclusive<i64>)>)> = None;
@@ -189,7 +190,7 @@ pub fn part2(input: &str) -> i64 {
             let to = mapping.next().unwrap();
             let from = mapping.next().unwrap();
             let len = mapping.next().unwrap();
-            v.push((to - from, from..=(from + len)));
+            v.push((to - from, from..=(from + len - 1)));
         }
     }
     if let Some((path, vec)) = current_map.take() {
@@ -207,79 +208,25 @@ pub fn part2(input: &str) -> i64 {
     );
     let location = map_ranges(&humidity, maps.get(&("humidity", "location")).unwrap());
 
-    location.into_iter().map(|v| *v.start()).min().unwrap()
-}
-
-fn map_ranges(
-    ranges: &[RangeInclusive<i64>],
-    map: &[(i64, RangeInclusive<i64>)],
-) -> Vec<RangeInclusive<i64>> {
-    ranges
-        .iter()
-        .map(|r| map_range(r.clone(), map))
-        .flatten()
-        .collect()
+    location.min().unwrap()
 }
-fn map_range(
-    mut range: RangeInclusive<i64>,
-    map: &[(i64, RangeInclusive<i64>)],
-) -> Vec<RangeInclusive<i64>> {
-    let mut result = Vec::<RangeInclusive<i64>>::new();
-
-    // First, any out-of-bounds regions get mapped to themselves
-    let min = *map.iter().map(|v| v.1.start()).min().unwrap();
-    let max = *map.iter().map(|v| v.1.end()).max().unwrap();
-    if *range.start() < min {
-        result.push(*range.start()..=(min - 1));
-        range = min..=*range.end();
-    }
-    if *range.end() > max {
-        result.push((max + 1).max(*range.start())..=*range.end());
-        range = *range.start()..=max;
-    }
-
-    // Then, any in-bounds regions get mapped with an offset
-    let mut range_before = range.clone();
-    while range.end() > range.start() {
-        for (offset, map_region) in map {
-            let Some(source_range) = range_intersect(&map_region, &range) else {
-                continue;
-            };
-
-            if source_range.start() != range.start() {
-                continue;
-            }
-            range = *source_range.end()..=*range.end();
 
-            let target_range = (source_range.start() + offset)..=(source_range.end() + offset);
-            result.push(target_range);
-        }
-
-        if range.end() == range.start() {
-            break;
-        }
-
-        // If we've gone through all the regions and not found a head section, we're in an
-        // inter-region gap in the map. Find how long the gap is, and push an identity-mapped
-        // section for that slice.
-        let Some(min) = map
-            .iter()
-            .filter(|v| *v.1.start() >= *range.start())
-            .map(|v| *v.1.start())
-            .min()
-        else {
-            panic!("missing head: {range:?} -> {map:?}");
-        };
-        result.push(*range.start()..=(min - 1));
-        range = min..=*range.end();
-
-        if range == range_before {
-            panic!("stuck on range: {range:?}");
-        }
-        range_before = range.clone();
+/// Moves the parts of `ranges` that some source range in `map` covers by that range's offset,
+/// and leaves the rest where they are.
+fn map_ranges(ranges: &RangeSet<i64>, map: &[(i64, RangeInclusive<i64>)]) -> RangeSet<i64> {
+    let mut unmapped = ranges.clone();
+    let mut mapped = vec![];
+    for (offset, source) in map {
+        let source = RangeSet::from(source.clone());
+        mapped.extend(
+            ranges
+                .intersection(&source)
+                .iter()
+                .map(|r| (r.start() + offset)..=(r.end() + offset)),
+        );
+        unmapped = unmapped.difference(&source);
     }
-
-    result
+    mapped.into_iter().chain(unmapped.iter()).collect()
 }
 
 /// Version 2.
@@ -672,8 +619,223 @@ fn parse_part2(input: &str) -> (Vec<RangeInclusive<i64>>, Vec<Vec<(i64, i64, i64
     (seeds, maps)
 }
 
+/// A map from one kind of number to another that shifts each of a few disjoint ranges by its own
+/// offset and leaves everything else alone, like a single almanac map. Composing them keeps
+/// them that shape, so the whole almanac can be flattened into one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PiecewiseMap {
+    /// `(start, end, offset)`, sorted, with no two overlapping. Only pieces that actually move
+    /// anything are kept, and neighbours that move by the same amount are merged.
+    pieces: Vec<(i64, i64, i64)>,
+}
+
+impl PiecewiseMap {
+    fn new(mut pieces: Vec<(i64, i64, i64)>) -> Self {
+        pieces.retain(|(start, end, offset)| start <= end && *offset != 0);
+        pieces.sort_unstable();
+        let mut merged: Vec<(i64, i64, i64)> = vec![];
+        for (start, end, offset) in pieces {
+            if let Some(last) = merged.last() {
+                assert!(last.1 < start, "{last:?} overlaps {:?}", (start, end));
+            }
+            match merged.last_mut() {
+                Some(last) if last.1 + 1 == start && last.2 == offset => last.1 = end,
+                _ => merged.push((start, end, offset)),
+            }
+        }
+        PiecewiseMap { pieces: merged }
+    }
+
+    /// One map from the almanac, as its `dest src len` lines. A header line is skipped if it's
+    /// there.
+    pub fn parse(layer: &str) -> Self {
+        PiecewiseMap::new(
+            layer
+                .lines()
+                .filter(|l| l.starts_with(|c: char| c.is_ascii_digit()))
+                .map(|line| {
+                    let (to, from, len) = line
+                        .split_whitespace()
+                        .map(|v| v.parse::<i64>().unwrap())
+                        .collect_tuple()
+                        .unwrap();
+                    (from, from + len - 1, to - from)
+                })
+                .collect(),
+        )
+    }
+
+    /// The pieces plus the gaps between them, which have an offset of 0, covering every `i64`.
+    fn cover(&self) -> Vec<(i64, i64, i64)> {
+        let mut cover = vec![];
+        let mut from = Some(i64::MIN);
+        for &(start, end, offset) in &self.pieces {
+            if let Some(gap_start) = from.filter(|gap_start| *gap_start < start) {
+                cover.push((gap_start, start - 1, 0));
+            }
+            cover.push((start, end, offset));
+            from = end.checked_add(1);
+        }
+        if let Some(gap_start) = from {
+            cover.push((gap_start, i64::MAX, 0));
+        }
+        cover
+    }
+
+    pub fn apply(&self, value: i64) -> i64 {
+        let ix = self.pieces.partition_point(|(_, end, _)| *end < value);
+        match self.pieces.get(ix) {
+            Some((start, _, offset)) if *start <= value => value + offset,
+            _ => value,
+        }
+    }
+
+    pub fn apply_set(&self, set: &RangeSet<i64>) -> RangeSet<i64> {
+        self.cover()
+            .into_iter()
+            .flat_map(|(start, end, offset)| {
+                set.intersection(&RangeSet::from(start..=end))
+                    .iter()
+                    .map(|r| (r.start() + offset)..=(r.end() + offset))
+                    .collect_vec()
+            })
+            .collect()
+    }
+
+    /// The map that does `self` and then `then`. Each piece of `self` lands somewhere in
+    /// `then`'s pieces and gaps, and gets cut up along them.
+    pub fn compose(&self, then: &PiecewiseMap) -> PiecewiseMap {
+        let then = then.cover();
+        let mut pieces = vec![];
+        for (start, end, offset) in self.cover() {
+            // the gaps don't move, so only bounded pieces get shifted here
+            let (lo, hi) = (start + offset, end + offset);
+            let first = then.partition_point(|(_, end, _)| *end < lo);
+            for &(then_start, then_end, then_offset) in &then[first..] {
+                if then_start > hi {
+                    break;
+                }
+                let (a, b) = (lo.max(then_start), hi.min(then_end));
+                pieces.push((a - offset, b - offset, offset + then_offset));
+            }
+        }
+        PiecewiseMap::new(pieces)
+    }
+}
+
+impl std::fmt::Display for PiecewiseMap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (start, end, offset) in &self.pieces {
+            writeln!(f, "{start}..={end} {offset:+}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The seed ranges, and every map in the almanac composed into one that goes straight from seed
+/// to location.
+fn composed_almanac(input: &str) -> (RangeSet<i64>, PiecewiseMap) {
+    let mut blocks = input.split("\n\n").filter(|b| !b.trim().is_empty());
+    let seeds = blocks
+        .next()
+        .unwrap()
+        .split_once(':')
+        .unwrap()
+        .1
+        .split_whitespace()
+        .map(|v| v.parse::<i64>().unwrap())
+        .tuples()
+        .map(|(start, num)| start..=(start + num - 1))
+        .collect();
+    let mut maps = blocks
+        .map(|block| {
+            let (name, _) = block
+                .trim_start()
+                .split_once(" map:")
+                .expect("a map without an `a-to-b map:` header");
+            let (from, to) = name.split_once("-to-").unwrap();
+            (from, (to, PiecewiseMap::parse(block)))
+        })
+        .collect::<HashMap<_, _>>();
+    // the maps don't have to come in order, so follow them by name
+    let mut map = PiecewiseMap::new(vec![]);
+    let mut at = "seed";
+    while at != "location" {
+        let (to, layer) = maps
+            .remove(at)
+            .unwrap_or_else(|| panic!("no map from {at}"));
+        map = map.compose(&layer);
+        at = to;
+    }
+    (seeds, map)
+}
+
+/// Version 7.
+///
+/// All seven maps flattened into one seed-to-location map first, so the seed ranges only go
+/// through a single map.
+pub fn part2_composed(input: &str) -> i64 {
+    let (seeds, map) = composed_almanac(input);
+    map.apply_set(&seeds).min().unwrap()
+}
+
+/// The composed seed-to-location map, one piece per line, for `--dump-composed`.
+pub fn dump_composed(input: &str) -> String {
+    let (seeds, map) = composed_almanac(input);
+    let seeds = seeds.iter().map(|r| format!("{r:?}")).join(", ");
+    format!("seeds: {seeds}\nseed-to-location map:\n{map}")
+}
+
+/// A random almanac over the numbers `0..size`: ten seed ranges, and seven maps that each shuffle
+/// a random partition of the number line around (leaving some parts unmapped).
+pub fn generate(size: usize, rng: &mut StdRng) -> String {
+    let size = size.max(2) as i64;
+    let mut out = String::from("seeds:");
+    for _ in 0..10 {
+        let start = rng.gen_range(0..size);
+        let len = rng.gen_range(1..=(size / 20).max(1)).min(size - start);
+        out.push_str(&format!(" {start} {len}"));
+    }
+    out.push('\n');
+
+    let names = [
+        "seed",
+        "soil",
+        "fertilizer",
+        "water",
+        "light",
+        "temperature",
+        "humidity",
+        "location",
+    ];
+    for (from, to) in names.iter().tuple_windows() {
+        let mut cuts = (0..rng.gen_range(2..=20).min(size - 1))
+            .map(|_| rng.gen_range(1..size))
+            .collect::<Vec<_>>();
+        cuts.extend([0, size]);
+        cuts.sort_unstable();
+        cuts.dedup();
+        let mut segments = cuts
+            .iter()
+            .tuple_windows()
+            .map(|(a, b)| (*a, b - a))
+            .collect_vec();
+        segments.shuffle(rng);
+        let mut dest = 0;
+        let mut lines = vec![];
+        for (src, len) in segments {
+            if rng.gen_range(0..10) != 0 {
+                lines.push(format!("{dest} {src} {len}"));
+            }
+            dest += len;
+        }
+        out.push_str(&format!("\n{from}-to-{to} map:\n{}\n", lines.join("\n")));
+    }
+    out
+}
+
 pub fn main(bench: bool) {
-    let input = std::fs::read_to_string("input/day05").unwrap();
+    let input = utils::input::read("input/day05");
 
     // // This one takes a couple minutes to run
     let begin = std::time::Instant::now();
@@ -686,7 +848,11 @@ pub fn main(bench: bool) {
 
     let iters = 1000;
 
-    let fns: [(&'static str, fn(&str) -> i64); 2] = [("part1", part1), ("part2", part2)];
+    let fns: [(&'static str, fn(&str) -> i64); 3] = [
+        ("part1", part1),
+        ("part2", part2),
+        ("part2_composed", part2_composed),
+    ];
 
     for (name, f) in fns {
         println!("  {name}: {}", f(&input));
@@ -784,18 +950,104 @@ humidity-to-location map:
 60 56 37
 56 93 4"#;
     assert_eq!(part2(input), 46);
+    assert_eq!(part2_composed(input), 46);
     assert_eq!(part2_brute(input), 46);
     assert_eq!(part2_brute_faster(input), 46);
 }
 
+#[test]
+fn test_compose() {
+    let mut rng = <StdRng as rand::SeedableRng>::seed_from_u64(5);
+    for size in [2, 10, 200, 1000] {
+        let input = generate(size, &mut rng);
+        let layers = input
+            .split("\n\n")
+            .skip(1)
+            .map(PiecewiseMap::parse)
+            .collect_vec();
+        let (seeds, composed) = composed_almanac(&input);
+        let size = size as i64;
+        for seed in -5..size + 5 {
+            let location = layers.iter().fold(seed, |id, layer| layer.apply(id));
+            assert_eq!(composed.apply(seed), location, "{seed} in\n{input}");
+        }
+        let locations = seeds
+            .iter()
+            .flatten()
+            .map(|seed| composed.apply(seed)..=composed.apply(seed))
+            .collect::<RangeSet<i64>>();
+        assert_eq!(composed.apply_set(&seeds), locations);
+
+        // the maps get chained by name, not by where they are in the almanac
+        let mut blocks = input.split("\n\n").collect_vec();
+        blocks[1..].shuffle(&mut rng);
+        assert_eq!(composed_almanac(&blocks.join("\n\n")).1, composed);
+    }
+}
+
 #[test]
 fn test_part1_facit() {
-    let input = std::fs::read_to_string("input/day05").unwrap();
+    let input = utils::input::read("input/day05");
     assert_eq!(part1(&input), 261668924);
 }
 
 #[test]
 fn test_part2_facit() {
-    let input = std::fs::read_to_string("input/day05").unwrap();
+    let input = utils::input::read("input/day05");
     assert_eq!(part2(&input), 24261545);
 }
+
+#[cfg(test)]
+fn arb_almanac() -> impl proptest::strategy::Strategy<Value = String> {
+    use proptest::prelude::*;
+    let seeds = prop::collection::vec((0i64..100, 1i64..20), 1..4);
+    // (gap since the last source range, length, destination), so sources never overlap
+    let map = prop::collection::vec((0i64..10, 1i64..30, 0i64..120), 1..4);
+    (seeds, prop::collection::vec(map, 7)).prop_map(|(seeds, maps)| {
+        let names = [
+            "seed",
+            "soil",
+            "fertilizer",
+            "water",
+            "light",
+            "temperature",
+            "humidity",
+            "location",
+        ];
+        let mut out = format!(
+            "seeds: {}\n",
+            seeds
+                .iter()
+                .map(|(start, len)| format!("{start} {len}"))
+                .join(" ")
+        );
+        for ((from, to), map) in names.iter().tuple_windows().zip(maps) {
+            out.push_str(&format!("\n{from}-to-{to} map:\n"));
+            let mut src = 0;
+            for (gap, len, dest) in map {
+                src += gap;
+                out.push_str(&format!("{dest} {src} {len}\n"));
+                src += len;
+            }
+        }
+        out
+    })
+}
+
+#[cfg(test)]
+proptest::proptest! {
+    #[test]
+    fn test_part2_variants_agree(input in arb_almanac()) {
+        utils::assert_variants_agree(
+            &input,
+            &[
+                ("part2", part2),
+                ("part2_composed", part2_composed),
+                ("part2_brute", part2_brute),
+                ("part2_brute_faster", part2_brute_faster),
+                ("part2_brute_faster_2", part2_brute_faster_2),
+                ("part2_brute_faster_3", part2_brute_faster_3),
+            ],
+        );
+    }
+}
@@ -1,4 +1,5 @@
 use itertools::Itertools;
+use rand::{rngs::StdRng, Rng};
 
 /// Brute-force solution.
 ///
@@ -108,8 +109,23 @@ pub fn part2(input: &str) -> i64 {
     result
 }
 
+/// Three or four random races that each take at most `size` milliseconds, with records that
+/// can be beaten.
+pub fn generate(size: usize, rng: &mut StdRng) -> String {
+    let size = size.max(2) as i64;
+    let (mut times, mut records) = (vec![], vec![]);
+    for _ in 0..rng.gen_range(3..=4) {
+        let time = rng.gen_range((size / 4).max(2)..=size);
+        let best = (time / 2) * (time - time / 2);
+        times.push(time);
+        records.push(rng.gen_range(0..best));
+    }
+    let row = |nums: &[i64]| nums.iter().map(|v| format!("{v:>5}")).join(" ");
+    format!("Time:    {}\nDistance:{}", row(&times), row(&records))
+}
+
 pub fn main(bench: bool) {
-    let input = std::fs::read_to_string("input/day06").unwrap();
+    let input = utils::input::read("input/day06");
 
     let iters = 10;
 
@@ -158,13 +174,13 @@ Distance:  9  40  200"#;
 
 #[test]
 fn test_part1_facit() {
-    let input = std::fs::read_to_string("input/day06").unwrap();
+    let input = utils::input::read("input/day06");
     assert_eq!(part1(&input), 2065338);
 }
 
 #[test]
 fn test_part2_facit() {
-    let input = std::fs::read_to_string("input/day06").unwrap();
+    let input = utils::input::read("input/day06");
     assert_eq!(part2(&input), 34934171);
     assert_eq!(part2_math(&input), 34934171);
 }
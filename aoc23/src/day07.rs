@@ -1,6 +1,8 @@
 use std::cmp::Reverse;
 
 use itertools::Itertools;
+use rand::{rngs::StdRng, Rng};
+use utils::gen;
 
 pub fn part1(input: &str) -> i64 {
     let mut games = input
@@ -140,8 +142,18 @@ pub fn part2_faster(input: &str) -> i64 {
     solve_faster(input, true)
 }
 
+/// `size` different random hands, each with a bid of up to 1000.
+pub fn generate(size: usize, rng: &mut StdRng) -> String {
+    const CARDS: &[u8] = b"23456789TJQKA";
+    let size = size.min(CARDS.len().pow(5));
+    gen::distinct_words(rng, size, 5, CARDS, &[])
+        .into_iter()
+        .map(|hand| format!("{hand} {}", rng.gen_range(1..=1000)))
+        .join("\n")
+}
+
 pub fn main(bench: bool) {
-    let input = std::fs::read_to_string("input/day07").unwrap();
+    let input = utils::input::read("input/day07");
 
     let iters = 1000;
 
@@ -198,14 +210,14 @@ QQQJA 483"#;
 
 #[test]
 fn test_part1_facit() {
-    let input = std::fs::read_to_string("input/day07").unwrap();
+    let input = utils::input::read("input/day07");
     assert_eq!(part1(&input), 250058342);
     assert_eq!(part1_faster(&input), 250058342);
 }
 
 #[test]
 fn test_part2_facit() {
-    let input = std::fs::read_to_string("input/day07").unwrap();
+    let input = utils::input::read("input/day07");
     assert_eq!(part2(&input), 250506580);
     assert_eq!(part2_faster(&input), 250506580);
 }
@@ -1,5 +1,12 @@
 use std::collections::HashMap;
 
+use itertools::Itertools;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng};
+use utils::{
+    assume::{self, Assumption},
+    gen,
+};
+
 pub fn part1(input: &str) -> i64 {
     let mut paths = HashMap::<&str, (&str, &str)>::new();
     let mut lines = input.lines();
@@ -24,7 +31,14 @@ pub fn part1(input: &str) -> i64 {
     result
 }
 
+/// Works for any network: see [`Ghost`] and [`first_all_on_z`].
 pub fn part2(input: &str) -> i64 {
+    first_all_on_z(input).expect("the ghosts are never all on ..Z nodes at once")
+}
+
+/// The first version, which is only right when every ghost's walk is a clean cycle from the
+/// start back to a single `..Z` node (see [`LCM_ASSUMPTIONS`]).
+pub fn part2_lcm(input: &str) -> i64 {
     let mut paths = HashMap::<&str, (&str, &str)>::new();
     let mut lines = input.lines();
     let instr = lines.next().unwrap().chars().cycle();
@@ -55,19 +69,251 @@ pub fn part2(input: &str) -> i64 {
         .fold(1, num::integer::lcm)
 }
 
+fn parse_network(input: &str) -> (&[u8], HashMap<&str, (&str, &str)>) {
+    let mut paths = HashMap::<&str, (&str, &str)>::new();
+    let mut lines = input.lines();
+    let instr = lines.next().unwrap().as_bytes();
+    lines.next();
+    for line in lines {
+        paths.insert(&line[0..=2], (&line[7..=9], &line[12..=14]));
+    }
+    (instr, paths)
+}
+
+/// One ghost's whole future. Where it is only depends on its node and how far into the
+/// instructions it is, so after at most `nodes * instructions` steps it's somewhere it's been
+/// before, and from then on it goes around the same loop forever.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ghost<'a> {
+    pub start: &'a str,
+    /// Step where the loop starts.
+    pub mu: usize,
+    /// Length of the loop.
+    pub lambda: usize,
+    /// Every step before `mu + lambda` where the ghost is on a `..Z` node.
+    pub hits: Vec<usize>,
+}
+
+impl Ghost<'_> {
+    fn on_z(&self, step: usize) -> bool {
+        let step = match step.checked_sub(self.mu) {
+            Some(into_loop) => self.mu + into_loop % self.lambda,
+            None => step,
+        };
+        self.hits.binary_search(&step).is_ok()
+    }
+}
+
+fn walk_ghost<'a>(
+    instr: &[u8],
+    paths: &HashMap<&'a str, (&'a str, &'a str)>,
+    start: &'a str,
+) -> Result<Ghost<'a>, String> {
+    let mut seen = HashMap::<(&str, usize), usize>::new();
+    let mut hits = vec![];
+    let mut pos = start;
+    loop {
+        let step = seen.len();
+        if let Some(mu) = seen.insert((pos, step % instr.len()), step) {
+            let lambda = step - mu;
+            return Ok(Ghost {
+                start,
+                mu,
+                lambda,
+                hits,
+            });
+        }
+        if pos.ends_with('Z') {
+            hits.push(step);
+        }
+        let (l, r) = paths
+            .get(pos)
+            .ok_or_else(|| format!("{start} walks to {pos}, which isn't in the network"))?;
+        pos = if instr[step % instr.len()] == b'L' {
+            l
+        } else {
+            r
+        };
+    }
+}
+
+/// Before every ghost is in its loop there are few enough steps to just check them all. After
+/// that each ghost is on `..Z` exactly at the steps congruent to one of its hits in the loop,
+/// modulo its loop length, so the answer is the smallest step that solves one such congruence
+/// for every ghost. Those get combined a ghost at a time with the generalized Chinese remainder
+/// theorem, which handles loop lengths that share factors.
+pub fn first_all_on_z(input: &str) -> Option<i64> {
+    let (instr, paths) = parse_network(input);
+    let ghosts = paths
+        .keys()
+        .filter(|v| v.ends_with('A'))
+        .sorted()
+        .map(|start| walk_ghost(instr, &paths, start))
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+
+    let settled = ghosts.iter().map(|g| g.mu).max()?;
+    if let Some(step) = (1..settled).find(|step| ghosts.iter().all(|g| g.on_z(*step))) {
+        return Some(step as i64);
+    }
+
+    // (remainder, modulus) pairs, any of which puts every ghost so far on ..Z
+    let mut classes = vec![(0, 1)];
+    for ghost in &ghosts {
+        let residues = ghost
+            .hits
+            .iter()
+            .filter(|hit| **hit >= ghost.mu)
+            .map(|hit| ((hit % ghost.lambda) as i128, ghost.lambda as i128))
+            .collect_vec();
+        classes = classes
+            .into_iter()
+            .cartesian_product(residues)
+            .filter_map(|(a, b)| crt(a, b))
+            .unique()
+            .collect();
+    }
+    let settled = settled as i128;
+    classes
+        .into_iter()
+        .map(|(r, m)| settled + (r - settled).rem_euclid(m))
+        .min()
+        .map(|step| step as i64)
+}
+
+/// Solves `x = a mod m` and `x = b mod n` together, giving `x mod lcm(m, n)`, or `None` if they
+/// contradict each other. `m` and `n` don't have to be coprime.
+fn crt((a, m): (i128, i128), (b, n): (i128, i128)) -> Option<(i128, i128)> {
+    // extended euclid: p * m + q * n = g
+    let (mut g, mut g1, mut p, mut p1) = (m, n, 1, 0);
+    while g1 != 0 {
+        let q = g / g1;
+        (g, g1) = (g1, g - q * g1);
+        (p, p1) = (p1, p - q * p1);
+    }
+    if (b - a) % g != 0 {
+        return None;
+    }
+    let lcm = m / g * n;
+    let x = a + (b - a) / g % (n / g) * p % (n / g) * m;
+    Some((x.rem_euclid(lcm), lcm))
+}
+
+/// What `part2_lcm` relies on. `part2` works for any network.
+pub static LCM_ASSUMPTIONS: &[Assumption] = &[Assumption {
+    part: 2,
+    name: "clean_ghost_cycles",
+    description: "every ghost is on a ..Z node exactly at the multiples of when it first gets to \
+        one, so the answer is the lcm of those first arrivals",
+    check: check_ghost_cycles,
+}];
+
+/// Walks every ghost until its (node, instruction) state repeats, which gives every step it will
+/// ever be on a `..Z` node, and checks those are exactly the multiples of the first one.
+fn check_ghost_cycles(input: &str) -> Result<(), String> {
+    let (instr, paths) = parse_network(input);
+    for start in paths.keys().filter(|v| v.ends_with('A')).sorted() {
+        let ghost = walk_ghost(instr, &paths, start)?;
+        let (arrivals, mu, end) = (ghost.hits, ghost.mu, ghost.mu + ghost.lambda);
+        let Some(&first) = arrivals.first() else {
+            return Err(format!("{start} never gets to a ..Z node"));
+        };
+        if first == 0 {
+            return Err(format!("{start} starts on a ..Z node"));
+        }
+        if (end - mu) % first != 0 {
+            return Err(format!(
+                "{start} first gets to ..Z after {first} steps, but then loops every {}",
+                end - mu
+            ));
+        }
+        let multiples = (1..)
+            .map(|k| k * first)
+            .take_while(|v| *v < end)
+            .collect_vec();
+        let same = arrivals
+            .iter()
+            .zip(&multiples)
+            .take_while(|(a, b)| a == b)
+            .count();
+        let problem = match (arrivals.get(same), multiples.get(same)) {
+            (Some(got), Some(want)) if got > want => format!("isn't on one after {want}"),
+            (Some(got), _) => format!("is also on one after {got}"),
+            (None, Some(want)) => format!("isn't on one after {want}"),
+            (None, None) => continue,
+        };
+        return Err(format!(
+            "{start} first gets to ..Z after {first} steps, but {problem}"
+        ));
+    }
+    Ok(())
+}
+
+/// A random network where `AAA` walks a plain chain to `ZZZ`, and five more ghosts each walk a
+/// cycle of between `size / 2` and `size` steps back to their `..Z` node. Every node on a ghost's
+/// cycle has a twin, and left and right lead to the twins of the next step, so the instructions
+/// never change how long a cycle is.
+pub fn generate(size: usize, rng: &mut StdRng) -> String {
+    // nodes in between never start with A or end with Z
+    const INNER: &[u8] = b"BCDEFGHIJKLMNOPQRSTUVWXY";
+    let size = size.max(2);
+    let instructions = (0..rng.gen_range(size / 2..=size))
+        .map(|_| if rng.gen() { 'L' } else { 'R' })
+        .collect::<String>();
+    let lens = (0..6)
+        .map(|_| rng.gen_range(size / 2..=size).max(1))
+        .collect::<Vec<_>>();
+    let inner_count = lens[0] - 1 + lens[1..].iter().map(|len| 2 * (len - 1)).sum::<usize>();
+    let mut inner = gen::distinct_words(rng, inner_count, 3, INNER, &[]).into_iter();
+    let mut ends = gen::distinct_words(rng, 10, 2, INNER, &[]).into_iter();
+
+    let mut lines = vec![];
+    // AAA's path is a chain where left and right are the same node
+    let mut chain = (1..lens[0]).map(|_| inner.next().unwrap()).collect_vec();
+    chain.push("ZZZ".to_string());
+    lines.push(format!("AAA = ({0}, {0})", chain[0]));
+    for (ix, node) in chain.iter().enumerate() {
+        let next = &chain[(ix + 1) % chain.len()];
+        lines.push(format!("{node} = ({next}, {next})"));
+    }
+    for len in &lens[1..] {
+        let start = format!("{}A", ends.next().unwrap());
+        let end = format!("{}Z", ends.next().unwrap());
+        let mut steps = (1..*len)
+            .map(|_| (inner.next().unwrap(), inner.next().unwrap()))
+            .collect_vec();
+        steps.push((end.clone(), end));
+        lines.push(format!("{start} = ({}, {})", steps[0].0, steps[0].1));
+        for (ix, (a, b)) in steps.iter().enumerate() {
+            let (l, r) = &steps[(ix + 1) % steps.len()];
+            lines.push(format!("{a} = ({l}, {r})"));
+            if a != b {
+                lines.push(format!("{b} = ({l}, {r})"));
+            }
+        }
+    }
+    lines.shuffle(rng);
+    format!("{instructions}\n\n{}", lines.join("\n"))
+}
+
 pub fn main(bench: bool) {
-    let input = std::fs::read_to_string("input/day08").unwrap();
+    let input = utils::input::read("input/day08");
 
     let iters = 1000;
 
-    let fns: [(&'static str, fn(&str) -> i64); 2] = [("part1", part1), ("part2", part2)];
+    let mut fns: Vec<(&'static str, fn(&str) -> i64)> =
+        vec![("part1", part1), ("part2", part2), ("part2_lcm", part2_lcm)];
+    if let Err(e) = assume::check(LCM_ASSUMPTIONS, 2, &input) {
+        println!("  part2_lcm: {e}");
+        fns.retain(|(name, _)| *name != "part2_lcm");
+    }
 
-    for (name, f) in fns {
+    for (name, f) in &fns {
         println!("  {name}: {}", f(&input));
     }
     println!("");
     if bench {
-        for (name, f) in fns {
+        for (name, f) in &fns {
             let begin = std::time::Instant::now();
             for _ in 0..iters {
                 f(&input);
@@ -121,16 +367,123 @@ fn test_part2_example() {
 22Z = (22B, 22B)
 XXX = (XXX, XXX)"#;
     assert_eq!(part2(input), 6);
+    assert_eq!(part2_lcm(input), 6);
+}
+
+#[test]
+fn test_part2_not_lcm() {
+    // 11A is on ..Z every other step from 2, 22A every third step from 1. The lcm of the first
+    // arrivals is 2, but 22A isn't on 22Z then.
+    let input = r#"L
+
+11A = (11B, 11B)
+11B = (11Z, 11Z)
+11Z = (11B, 11B)
+22A = (22Z, 22Z)
+22Z = (22B, 22B)
+22B = (22C, 22C)
+22C = (22Z, 22Z)"#;
+    assert_eq!(part2(input), 4);
+    assert_eq!(part2_lcm(input), 2);
+    assert!(assume::check(LCM_ASSUMPTIONS, 2, input).is_err());
+
+    // 11A is on ..Z at even steps, 22A at odd ones
+    let input = r#"L
+
+11A = (11Z, 11Z)
+11Z = (11B, 11B)
+11B = (11Z, 11Z)
+22A = (22B, 22B)
+22B = (22Z, 22Z)
+22Z = (22C, 22C)
+22C = (22Z, 22Z)"#;
+    assert_eq!(first_all_on_z(input), None);
+}
+
+#[test]
+fn test_crt() {
+    assert_eq!(crt((2, 3), (3, 5)), Some((8, 15)));
+    assert_eq!(crt((1, 4), (3, 6)), Some((9, 12)));
+    assert_eq!(crt((0, 4), (1, 6)), None);
+    assert_eq!(crt((5, 7), (5, 7)), Some((5, 7)));
+    assert_eq!(crt((0, 1), (-1, 5)), Some((4, 5)));
+}
+
+#[test]
+fn test_part2_brute() {
+    // tiny random networks where ghosts wander into and around arbitrary loops
+    fn brute(input: &str, limit: usize) -> Option<i64> {
+        let (instr, paths) = parse_network(input);
+        let mut ghosts = paths.keys().filter(|v| v.ends_with('A')).collect_vec();
+        for step in 0..limit {
+            if step > 0 && ghosts.iter().all(|g| g.ends_with('Z')) {
+                return Some(step as i64);
+            }
+            for g in &mut ghosts {
+                let (l, r) = &paths[*g];
+                *g = if instr[step % instr.len()] == b'L' {
+                    l
+                } else {
+                    r
+                };
+            }
+        }
+        None
+    }
+    let mut rng = <StdRng as rand::SeedableRng>::seed_from_u64(39);
+    let names = [
+        "1AA", "2AA", "3AA", "BBZ", "CCZ", "DDD", "EEE", "FFZ", "GGG",
+    ];
+    for _ in 0..500 {
+        let instructions = (0..rng.gen_range(1..=4))
+            .map(|_| if rng.gen() { 'L' } else { 'R' })
+            .collect::<String>();
+        let lines = names
+            .iter()
+            .map(|n| {
+                let l = names[rng.gen_range(0..names.len())];
+                let r = names[rng.gen_range(0..names.len())];
+                format!("{n} = ({l}, {r})")
+            })
+            .join("\n");
+        let input = format!("{instructions}\n\n{lines}");
+        // every state of all three ghosts together repeats within this many steps
+        let limit = (names.len() * instructions.len()).pow(3) + 1;
+        assert_eq!(first_all_on_z(&input), brute(&input, limit), "{input}");
+    }
+}
+
+#[test]
+fn test_assumptions() {
+    let input = utils::input::read("input/day08");
+    assert_eq!(assume::check(LCM_ASSUMPTIONS, 2, &input), Ok(()));
+    let mut rng = <StdRng as rand::SeedableRng>::seed_from_u64(8);
+    assert_eq!(
+        assume::check(LCM_ASSUMPTIONS, 2, &generate(50, &mut rng)),
+        Ok(())
+    );
+
+    let input = r#"LR
+
+11A = (11Z, 11Z)
+11Z = (11B, 11B)
+11B = (11B, 11B)"#;
+    let violation = assume::check(LCM_ASSUMPTIONS, 2, input).unwrap_err();
+    assert_eq!(
+        violation.reason,
+        "11A first gets to ..Z after 1 steps, but isn't on one after 2"
+    );
 }
 
 #[test]
 fn test_part1_facit() {
-    let input = std::fs::read_to_string("input/day08").unwrap();
+    let input = utils::input::read("input/day08");
     assert_eq!(part1(&input), 21797);
 }
 
 #[test]
 fn test_part2_facit() {
-    let input = std::fs::read_to_string("input/day08").unwrap();
+    let input = utils::input::read("input/day08");
     assert_eq!(part2(&input), 23977527174353);
+    assert_eq!(part2_lcm(&input), 23977527174353);
 }
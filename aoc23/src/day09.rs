@@ -1,4 +1,5 @@
 use itertools::Itertools;
+use rand::{rngs::StdRng, Rng};
 
 pub fn part1(input: &str) -> i64 {
     let serieses = input
@@ -56,8 +57,30 @@ fn predict_backwards(series: &[i64]) -> i64 {
     series.first().unwrap() - delta
 }
 
+/// `size` random sequences of 21 values, each from a polynomial of degree at most 12.
+pub fn generate(size: usize, rng: &mut StdRng) -> String {
+    let mut lines = vec![];
+    for _ in 0..size {
+        // coefficients in the binomial basis, so the values are all integers
+        let coefs = (0..=rng.gen_range(0..=12))
+            .map(|_| rng.gen_range(-9..=9))
+            .collect_vec();
+        let values = (0..21i64).map(|x| {
+            let mut binom = 1;
+            let mut value = 0;
+            for (k, c) in coefs.iter().enumerate() {
+                value += c * binom;
+                binom = binom * (x - k as i64) / (k as i64 + 1);
+            }
+            value
+        });
+        lines.push(values.map(|v| v.to_string()).join(" "));
+    }
+    lines.join("\n")
+}
+
 pub fn main(bench: bool) {
-    let input = std::fs::read_to_string("input/day09").unwrap();
+    let input = utils::input::read("input/day09");
 
     let iters = 1000;
 
@@ -103,12 +126,12 @@ fn test_part2_example() {
 
 #[test]
 fn test_part1_facit() {
-    let input = std::fs::read_to_string("input/day09").unwrap();
+    let input = utils::input::read("input/day09");
     assert_eq!(part1(&input), 1955513104);
 }
 
 #[test]
 fn test_part2_facit() {
-    let input = std::fs::read_to_string("input/day09").unwrap();
+    let input = utils::input::read("input/day09");
     assert_eq!(part2(&input), 1131);
 }
@@ -1,6 +1,8 @@
 use std::collections::{HashMap, HashSet, VecDeque};
 
 use itertools::Itertools;
+use rand::{rngs::StdRng, Rng};
+use utils::{gen, Dir, PosUtils};
 
 pub fn part1(input: &str) -> i64 {
     let mut start: Option<(i64, i64)> = None;
@@ -238,8 +240,52 @@ pub fn part2(input: &str) -> i64 {
     result
 }
 
+/// A `size` by `size` field of junk pipe with one random loop through it.
+pub fn generate(size: usize, rng: &mut StdRng) -> String {
+    let size = size.max(2);
+    let mut grid = gen::grid(
+        rng,
+        size,
+        size,
+        &[
+            ('.', 3),
+            ('|', 1),
+            ('-', 1),
+            ('L', 1),
+            ('J', 1),
+            ('7', 1),
+            ('F', 1),
+        ],
+    );
+    let points = gen::random_loop(rng, size, size);
+    for (ix, &(row, col)) in points.iter().enumerate() {
+        let prev = points[(ix + points.len() - 1) % points.len()];
+        let next = points[(ix + 1) % points.len()];
+        let a = (row, col).dir_of(&prev);
+        let b = (row, col).dir_of(&next);
+        grid[row][col] = match (a.min(b), a.max(b)) {
+            (Dir::Up, Dir::Down) => '|',
+            (Dir::Right, Dir::Left) => '-',
+            (Dir::Up, Dir::Right) => 'L',
+            (Dir::Up, Dir::Left) => 'J',
+            (Dir::Down, Dir::Left) => '7',
+            (Dir::Right, Dir::Down) => 'F',
+            _ => unreachable!(),
+        };
+    }
+    let start = points[rng.gen_range(0..points.len())];
+    grid[start.0][start.1] = 'S';
+    // junk right next to the start could look like a second way out of it
+    for pos in start.neighbors_in(&grid) {
+        if !points.contains(&pos) {
+            grid[pos.0][pos.1] = '.';
+        }
+    }
+    gen::grid_to_string(&grid)
+}
+
 pub fn main(bench: bool) {
-    let input = std::fs::read_to_string("input/day10").unwrap();
+    let input = utils::input::read("input/day10");
 
     let iters = 100;
 
@@ -307,12 +353,12 @@ L--J.L7...LJS7F-7L7.
 
 #[test]
 fn test_part1_facit() {
-    let input = std::fs::read_to_string("input/day10").unwrap();
+    let input = utils::input::read("input/day10");
     assert_eq!(part1(&input), 6690);
 }
 
 #[test]
 fn test_part2_facit() {
-    let input = std::fs::read_to_string("input/day10").unwrap();
+    let input = utils::input::read("input/day10");
     assert_eq!(part2(&input), 525);
 }
@@ -1,4 +1,6 @@
 use itertools::Itertools;
+use rand::{rngs::StdRng, Rng};
+use utils::gen;
 
 fn solve(input: &str, factor: i64) -> i64 {
     let mut rows = 0i64;
@@ -60,8 +62,17 @@ pub fn part2(input: &str) -> i64 {
     solve(input, 1000000)
 }
 
+/// A `size` by `size` image with galaxies scattered sparsely across it.
+pub fn generate(size: usize, rng: &mut StdRng) -> String {
+    let size = size.max(1);
+    let mut grid = gen::grid(rng, size, size, &[('.', 45), ('#', 1)]);
+    let (row, col) = (rng.gen_range(0..size), rng.gen_range(0..size));
+    grid[row][col] = '#';
+    gen::grid_to_string(&grid)
+}
+
 pub fn main(bench: bool) {
-    let input = std::fs::read_to_string("input/day11").unwrap();
+    let input = utils::input::read("input/day11");
 
     let iters = 100;
 
@@ -121,12 +132,12 @@ fn test_part2_example() {
 
 #[test]
 fn test_part1_facit() {
-    let input = std::fs::read_to_string("input/day11").unwrap();
+    let input = utils::input::read("input/day11");
     assert_eq!(part1(&input), 9312968);
 }
 
 #[test]
 fn test_part2_facit() {
-    let input = std::fs::read_to_string("input/day11").unwrap();
+    let input = utils::input::read("input/day11");
     assert_eq!(part2(&input), 597714117556);
 }
@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 
 use itertools::Itertools;
+use rand::{rngs::StdRng, Rng};
 
 pub fn part1(input: &str) -> i64 {
     let mut result = 0;
@@ -320,8 +321,37 @@ pub fn part2_dp(input: &str) -> i64 {
     result
 }
 
+/// `size` random condition records: a real arrangement of springs with about half of them
+/// replaced by `?`.
+pub fn generate(size: usize, rng: &mut StdRng) -> String {
+    let mut lines = vec![];
+    for _ in 0..size {
+        let len = rng.gen_range(3..=20);
+        let mut springs = vec!['.'; len];
+        let mut groups = vec![];
+        let mut pos = rng.gen_range(0..=2);
+        while pos < len {
+            let group = rng.gen_range(1..=6.min(len - pos));
+            springs[pos..pos + group].fill('#');
+            groups.push(group);
+            pos += group + 1 + rng.gen_range(0..=2);
+        }
+        for c in springs.iter_mut() {
+            if rng.gen() {
+                *c = '?';
+            }
+        }
+        lines.push(format!(
+            "{} {}",
+            springs.into_iter().collect::<String>(),
+            groups.iter().join(",")
+        ));
+    }
+    lines.join("\n")
+}
+
 pub fn main(bench: bool) {
-    let input = std::fs::read_to_string("input/day12").unwrap();
+    let input = utils::input::read("input/day12");
 
     let iters = 100;
 
@@ -415,14 +445,41 @@ fn test_part2_example() {
 
 #[test]
 fn test_part1_facit() {
-    let input = std::fs::read_to_string("input/day12").unwrap();
+    let input = utils::input::read("input/day12");
     assert_eq!(part1(&input), 7506);
     assert_eq!(part1_dfa(&input), 7506);
 }
 
 #[test]
 fn test_part2_facit() {
-    let input = std::fs::read_to_string("input/day12").unwrap();
+    let input = utils::input::read("input/day12");
     assert_eq!(part2(&input), 548241300348335);
     assert_eq!(part2_dfa(&input), 548241300348335);
 }
+
+#[cfg(test)]
+fn arb_records() -> impl proptest::strategy::Strategy<Value = String> {
+    use proptest::prelude::*;
+    let record = ("[.#?]{1,12}", prop::collection::vec(1usize..4, 1..4))
+        .prop_map(|(springs, groups)| format!("{springs} {}", groups.iter().join(",")));
+    prop::collection::vec(record, 1..4).prop_map(|lines| lines.join("\n"))
+}
+
+#[cfg(test)]
+proptest::proptest! {
+    #[test]
+    fn test_part1_variants_agree(input in arb_records()) {
+        utils::assert_variants_agree(
+            &input,
+            &[("part1", part1), ("part1_dfa", part1_dfa), ("part1_dp", part1_dp)],
+        );
+    }
+
+    #[test]
+    fn test_part2_variants_agree(input in arb_records()) {
+        utils::assert_variants_agree(
+            &input,
+            &[("part2", part2), ("part2_dfa", part2_dfa), ("part2_dp", part2_dp)],
+        );
+    }
+}
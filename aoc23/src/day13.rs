@@ -1,4 +1,6 @@
-use utils::Vec2dUtils;
+use itertools::Itertools;
+use rand::{rngs::StdRng, Rng};
+use utils::{gen, Vec2dUtils};
 
 static INPUT_FILE: &str = "input/day13";
 
@@ -172,8 +174,65 @@ fn find_smudge_iters(section: &[Vec<char>]) -> i64 {
     // 0
 }
 
+/// `size` random patterns, each with exactly one perfect reflection line and exactly one line
+/// that is a single smudge away from being one.
+pub fn generate(size: usize, rng: &mut StdRng) -> String {
+    // how many tiles differ across each vertical line of a pattern
+    fn mismatches(p: &[Vec<char>]) -> Vec<usize> {
+        let w = p[0].len();
+        (1..w)
+            .map(|ix| {
+                let len = ix.min(w - ix);
+                p.iter()
+                    .map(|line| {
+                        let (l, r) = (&line[(ix - len)..ix], &line[ix..(ix + len)]);
+                        l.iter().zip(r.iter().rev()).filter(|(a, b)| a != b).count()
+                    })
+                    .sum()
+            })
+            .collect()
+    }
+
+    let mut patterns = vec![];
+    while patterns.len() < size {
+        let (h, w) = (rng.gen_range(5..=17), rng.gen_range(5..=17));
+        // perfect vertical line after column `v`, smudged horizontal line after row `s`
+        let v = rng.gen_range(1..=(w - 1) / 2);
+        let s = rng.gen_range(1..h);
+        let mut p: Vec<Vec<char>> = vec![];
+        for row in 0..h {
+            if row >= s && row < 2 * s {
+                p.push(p[2 * s - 1 - row].clone());
+                continue;
+            }
+            let mut line = (0..w)
+                .map(|_| if rng.gen() { '#' } else { '.' })
+                .collect_vec();
+            for col in v..2 * v {
+                line[col] = line[2 * v - 1 - col];
+            }
+            p.push(line);
+        }
+        // the smudge goes outside the vertical reflection, so that one stays perfect
+        let row = rng.gen_range(s..h.min(2 * s));
+        let col = rng.gen_range(2 * v..w);
+        p[row][col] = if p[row][col] == '#' { '.' } else { '#' };
+
+        let (vertical, horizontal) = (mismatches(&p), mismatches(&p.transpose()));
+        let all = vertical.iter().chain(horizontal.iter());
+        if all.clone().filter(|n| **n == 0).count() != 1 || all.filter(|n| **n == 1).count() != 1 {
+            continue;
+        }
+        if rng.gen() {
+            p = p.transpose();
+        }
+        patterns.push(gen::grid_to_string(&p));
+    }
+    patterns.join("\n\n")
+}
+
 pub fn main(bench: bool) {
-    let input = std::fs::read_to_string(INPUT_FILE).unwrap();
+    let input = utils::input::read(INPUT_FILE);
 
     let iters = 100;
 
@@ -247,12 +306,21 @@ fn test_part2_example() {
 
 #[test]
 fn test_part1_facit() {
-    let input = std::fs::read_to_string(INPUT_FILE).unwrap();
+    let input = utils::input::read(INPUT_FILE);
     assert_eq!(part1(&input), 27505);
 }
 
 #[test]
 fn test_part2_facit() {
-    let input = std::fs::read_to_string(INPUT_FILE).unwrap();
+    let input = utils::input::read(INPUT_FILE);
     assert_eq!(part2(&input), 22906);
 }
+
+#[test]
+fn test_generate() {
+    let mut rng = rand::SeedableRng::seed_from_u64(7);
+    for pattern in generate(50, &mut rng).split("\n\n") {
+        assert!(part1(pattern) > 0, "no reflection in:\n{pattern}");
+        assert!(part2(pattern) > 0, "no smudged reflection in:\n{pattern}");
+    }
+}
@@ -1,6 +1,7 @@
-use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
 
-use utils::Vec2dUtils;
+use rand::rngs::StdRng;
+use utils::{cycle, gen, Vec2dUtils};
 
 static INPUT_FILE: &str = "input/day14";
 #[allow(dead_code)]
@@ -15,7 +16,175 @@ O.#..O.#.#
 #....###..
 #OO..#...."#;
 
+/// Platforms taller than a column's `u128` fit in (the real ones are 100 rows) are tilted on the
+/// grid instead.
+const MAX_BITBOARD_HEIGHT: usize = 128;
+
 pub fn part1(input: &str) -> i64 {
+    if input.lines().count() > MAX_BITBOARD_HEIGHT {
+        return part1_grid(input);
+    }
+    let (platform, mut rocks) = Platform::parse(input);
+    platform.tilt_north(&mut rocks.0);
+    platform.load(&rocks)
+}
+
+pub fn part2(input: &str) -> i64 {
+    if input.lines().count() > MAX_BITBOARD_HEIGHT {
+        return part2_grid(input);
+    }
+    let (platform, rocks) = Platform::parse(input);
+    platform.load(&cycle::state_after(1_000_000_000, rocks, |rocks| {
+        platform.spin(rocks)
+    }))
+}
+
+/// Where the cube rocks are, which never changes, as one bitset per column with the north edge
+/// at bit 0.
+#[derive(Debug, Clone)]
+struct Platform {
+    h: usize,
+    cube: Vec<u128>,
+    /// The runs of cells between cube rocks (and the edges) in each column, as masks.
+    segments: Vec<Vec<u128>>,
+}
+
+/// The round rocks, laid out like [`Platform::cube`]. Hashes to a single `u64` mixed from the
+/// columns, which is most of what cycle detection spends its time on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Rocks(Vec<u128>);
+
+impl Hash for Rocks {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let hash = self.0.iter().fold(0u64, |hash, col| {
+            (hash.rotate_left(5) ^ *col as u64 ^ (*col >> 64) as u64)
+                .wrapping_mul(0x517c_c1b7_2722_0a95)
+        });
+        state.write_u64(hash);
+    }
+}
+
+/// The lowest `n` bits.
+fn low_bits(n: u32) -> u128 {
+    u128::MAX.checked_shr(128 - n).unwrap_or(0)
+}
+
+impl Platform {
+    fn parse(input: &str) -> (Platform, Rocks) {
+        let grid = input
+            .lines()
+            .map(|line| line.as_bytes().to_vec())
+            .collect::<Vec<_>>()
+            .transpose();
+        let h = grid[0].len();
+        assert!(
+            h <= MAX_BITBOARD_HEIGHT,
+            "the platform is {h} rows tall, which doesn't fit in a u128"
+        );
+        let bits = |col: &[u8], rock| {
+            col.iter()
+                .enumerate()
+                .filter(|(_, c)| **c == rock)
+                .fold(0, |bits, (row, _)| bits | 1 << row)
+        };
+        let cube = grid
+            .iter()
+            .map(|col| bits(col, b'#'))
+            .collect::<Vec<u128>>();
+        let round = grid.iter().map(|col| bits(col, b'O')).collect();
+        let segments = cube
+            .iter()
+            .map(|cube| {
+                let mut segments = vec![];
+                let mut start = 0;
+                for row in (0..h).filter(|row| cube & 1 << row != 0).chain([h]) {
+                    if row > start {
+                        segments.push(low_bits((row - start) as u32) << start);
+                    }
+                    start = row + 1;
+                }
+                segments
+            })
+            .collect();
+        (Platform { h, cube, segments }, Rocks(round))
+    }
+
+    /// Packs the rocks in each run of a column against its north end, by keeping as many of the
+    /// run's lowest bits as there are rocks in it.
+    fn tilt_north(&self, round: &mut [u128]) {
+        for (col, segments) in round.iter_mut().zip(&self.segments) {
+            let mut tilted = 0;
+            for segment in segments {
+                let rocks = (*col & segment).count_ones();
+                tilted |= segment & !segment.checked_shl(rocks).unwrap_or(0);
+            }
+            *col = tilted;
+        }
+    }
+
+    fn tilt_south(&self, round: &mut [u128]) {
+        for (col, segments) in round.iter_mut().zip(&self.segments) {
+            let mut tilted = 0;
+            for segment in segments {
+                let rocks = (*col & segment).count_ones();
+                tilted |= segment & !segment.checked_shr(rocks).unwrap_or(0);
+            }
+            *col = tilted;
+        }
+    }
+
+    /// Going west to east, moves every row's rocks in a column one column over for as long as any
+    /// of them can, all rows at once. The columns west of it are already tilted, so nothing there
+    /// moves out of the way.
+    fn tilt_west(&self, round: &mut [u128]) {
+        for c in 1..round.len() {
+            for k in (1..=c).rev() {
+                let moving = round[k] & !(round[k - 1] | self.cube[k - 1]);
+                if moving == 0 {
+                    break;
+                }
+                round[k] ^= moving;
+                round[k - 1] |= moving;
+            }
+        }
+    }
+
+    fn tilt_east(&self, round: &mut [u128]) {
+        for c in (0..round.len() - 1).rev() {
+            for k in c..round.len() - 1 {
+                let moving = round[k] & !(round[k + 1] | self.cube[k + 1]);
+                if moving == 0 {
+                    break;
+                }
+                round[k] ^= moving;
+                round[k + 1] |= moving;
+            }
+        }
+    }
+
+    fn spin(&self, rocks: &Rocks) -> Rocks {
+        let mut round = rocks.0.clone();
+        self.tilt_north(&mut round);
+        self.tilt_west(&mut round);
+        self.tilt_south(&mut round);
+        self.tilt_east(&mut round);
+        Rocks(round)
+    }
+
+    /// Each rock weighs as many rows as there are from it to the south edge, counting its own.
+    fn load(&self, rocks: &Rocks) -> i64 {
+        let mut load = 0;
+        for mut col in rocks.0.iter().copied() {
+            while col != 0 {
+                load += self.h - col.trailing_zeros() as usize;
+                col &= col - 1;
+            }
+        }
+        load as i64
+    }
+}
+
+pub fn part1_grid(input: &str) -> i64 {
     let grid: Vec<Vec<char>> = input.lines().map(|line| line.chars().collect()).collect();
 
     // first, tilt north
@@ -52,30 +221,9 @@ pub fn part1(input: &str) -> i64 {
     result
 }
 
-pub fn part2(input: &str) -> i64 {
-    let mut grid: Vec<Vec<char>> = input.lines().map(|line| line.chars().collect()).collect();
-
-    let mut seen = HashMap::<Vec<Vec<char>>, usize>::new();
-
-    let mut cycle_ix: Option<(usize, usize)> = None;
-    for ix in 0..1_000_000_000 {
-        if let Some(start) = seen.get(&grid) {
-            cycle_ix = Some((*start, ix));
-            break;
-        }
-        seen.insert(grid.clone(), ix);
-        grid = spin_cycle(grid);
-    }
-
-    // if we found a cycle, we need to run some logic to spin up from the cycle
-    if let Some((cycle_start, cycle_end)) = cycle_ix {
-        let iters = (1_000_000_000 - cycle_start) % (cycle_end - cycle_start);
-        for _ in 0..iters {
-            grid = spin_cycle(grid);
-        }
-    }
-
-    grid.transpose();
+pub fn part2_grid(input: &str) -> i64 {
+    let grid: Vec<Vec<char>> = input.lines().map(|line| line.chars().collect()).collect();
+    let grid = cycle::state_after(1_000_000_000, grid, |grid| spin_cycle(grid));
 
     // then, calculate all the weights
     let mut result = 0;
@@ -90,7 +238,8 @@ pub fn part2(input: &str) -> i64 {
     result
 }
 
-fn spin_cycle(mut grid: Vec<Vec<char>>) -> Vec<Vec<char>> {
+fn spin_cycle(grid: &[Vec<char>]) -> Vec<Vec<char>> {
+    let mut grid = grid.to_vec();
     for _ in 0..4 {
         grid = rot90(&tilt_north(&grid));
     }
@@ -133,12 +282,27 @@ fn tilt_north(grid: &[Vec<char>]) -> Vec<Vec<char>> {
     grid.transpose()
 }
 
+/// A random `size` by `size` platform of round and cube-shaped rocks.
+pub fn generate(size: usize, rng: &mut StdRng) -> String {
+    gen::grid_to_string(&gen::grid(
+        rng,
+        size,
+        size,
+        &[('.', 10), ('O', 4), ('#', 3)],
+    ))
+}
+
 pub fn main(bench: bool) {
-    let input = std::fs::read_to_string(INPUT_FILE).unwrap();
+    let input = utils::input::read(INPUT_FILE);
 
     let iters = 10;
 
-    let fns: Vec<(&'static str, fn(&str) -> i64)> = vec![("part1", part1), ("part2", part2)];
+    let fns: Vec<(&'static str, fn(&str) -> i64)> = vec![
+        ("part1", part1),
+        ("part1_grid", part1_grid),
+        ("part2", part2),
+        ("part2_grid", part2_grid),
+    ];
 
     for (name, f) in &fns {
         println!("  {name}: {}", f(&input));
@@ -172,14 +336,67 @@ fn test_part2_example() {
     assert_eq!(part2(EXAMPLE_INPUT), 64);
 }
 
+#[cfg(test)]
+fn arb_platform() -> impl proptest::strategy::Strategy<Value = String> {
+    use proptest::prelude::*;
+    (any::<u64>(), 1usize..40)
+        .prop_map(|(seed, size)| generate(size, &mut rand::SeedableRng::seed_from_u64(seed)))
+}
+
+#[cfg(test)]
+proptest::proptest! {
+    #[test]
+    fn test_variants_agree(input in arb_platform()) {
+        utils::assert_variants_agree(&input, &[("part1", part1), ("part1_grid", part1_grid)]);
+        utils::assert_variants_agree(&input, &[("part2", part2), ("part2_grid", part2_grid)]);
+    }
+}
+
+#[test]
+fn test_tall_platform() {
+    // a single rock at the bottom of a column too tall for the bitboards
+    let input = format!("{}O", ".\n".repeat(129));
+    assert_eq!(part1(&input), 130);
+    assert_eq!(part2(&input), 1);
+}
+
+#[test]
+fn test_tilts() {
+    let (platform, mut rocks) = Platform::parse("O.#.O\n..O..\n.O..#\n#..O.");
+    let show = |rocks: &Rocks| {
+        (0..4)
+            .map(|row| {
+                (0..5)
+                    .map(
+                        |col| match (rocks.0[col] >> row & 1, platform.cube[col] >> row & 1) {
+                            (1, _) => 'O',
+                            (_, 1) => '#',
+                            _ => '.',
+                        },
+                    )
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    platform.tilt_south(&mut rocks.0);
+    assert_eq!(show(&rocks), "..#..\n....O\nO...#\n#OOO.");
+    platform.tilt_west(&mut rocks.0);
+    assert_eq!(show(&rocks), "..#..\nO....\nO...#\n#OOO.");
+    platform.tilt_north(&mut rocks.0);
+    assert_eq!(show(&rocks), "OO#O.\nO.O..\n....#\n#....");
+    platform.tilt_east(&mut rocks.0);
+    assert_eq!(show(&rocks), "OO#.O\n...OO\n....#\n#....");
+}
+
 #[test]
 fn test_part1_facit() {
-    let input = std::fs::read_to_string(INPUT_FILE).unwrap();
+    let input = utils::input::read(INPUT_FILE);
     assert_eq!(part1(&input), 113525);
 }
 
 #[test]
 fn test_part2_facit() {
-    let input = std::fs::read_to_string(INPUT_FILE).unwrap();
+    let input = utils::input::read(INPUT_FILE);
     assert_eq!(part2(&input), 101292);
 }
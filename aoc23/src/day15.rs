@@ -1,3 +1,7 @@
+use itertools::Itertools;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng};
+use utils::gen;
+
 static INPUT_FILE: &str = "input/day15";
 #[allow(dead_code)]
 static EXAMPLE_INPUT: &str = r#"rn=1,cm-,qp=3,cm=2,qp-,pc=4,ot=9,ab=5,pc-,pc=6,ot=7"#;
@@ -51,8 +55,28 @@ pub fn part2(input: &str) -> i64 {
     result
 }
 
+/// `size` random steps, reusing a pool of about `size / 3` labels.
+pub fn generate(size: usize, rng: &mut StdRng) -> String {
+    let labels = (0..size / 3 + 1)
+        .map(|_| {
+            let len = rng.gen_range(2..=6);
+            gen::word(rng, len, gen::LOWERCASE)
+        })
+        .collect_vec();
+    (0..size)
+        .map(|_| {
+            let label = labels.choose(rng).unwrap();
+            if rng.gen_range(0..10) < 4 {
+                format!("{label}-")
+            } else {
+                format!("{label}={}", rng.gen_range(1..=9))
+            }
+        })
+        .join(",")
+}
+
 pub fn main(bench: bool) {
-    let input = std::fs::read_to_string(INPUT_FILE).unwrap();
+    let input = utils::input::read(INPUT_FILE);
 
     let iters = 10;
 
@@ -97,12 +121,12 @@ fn test_part2_example() {
 
 #[test]
 fn test_part1_facit() {
-    let input = std::fs::read_to_string(INPUT_FILE).unwrap();
+    let input = utils::input::read(INPUT_FILE);
     assert_eq!(part1(&input), 506437);
 }
 
 #[test]
 fn test_part2_facit() {
-    let input = std::fs::read_to_string(INPUT_FILE).unwrap();
+    let input = utils::input::read(INPUT_FILE);
     assert_eq!(part2(&input), 288521);
 }
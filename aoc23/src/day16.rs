@@ -1,8 +1,11 @@
 use std::collections::{HashMap, HashSet, VecDeque};
 
+use rand::rngs::StdRng;
 use rayon::iter::{IntoParallelIterator, ParallelIterator};
 use rustc_hash::FxHashSet;
-use utils::{separate_thousands, Dir, Grid, Pos, PosUtils, SGrid, Vec2dUtils};
+use utils::{
+    event, gen, separate_thousands, trace::Level, Dir, Grid, Pos, PosUtils, SGrid, Vec2dUtils,
+};
 
 static INPUT_FILE: &str = "input/day16";
 #[allow(dead_code)]
@@ -24,18 +27,18 @@ pub fn part1(input: &str) -> i64 {
     traverse(&mut grid, (0, 0), Dir::Right, &mut seen);
     let seen = seen.into_iter().map(|v| v.0).collect::<HashSet<_>>();
 
-    if cfg!(test) {
-        for row in 0..grid.len() {
-            for col in 0..grid[0].len() {
-                if seen.contains(&(row, col)) {
-                    print!("#");
-                } else {
-                    print!(".");
-                }
-            }
-            println!("");
-        }
-    }
+    event!(
+        Level::Debug,
+        "energized",
+        grid = (0..grid.len())
+            .map(|row| {
+                (0..grid[0].len())
+                    .map(|col| if seen.contains(&(row, col)) { '#' } else { '.' })
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    );
 
     seen.len() as i64
 }
@@ -79,6 +82,13 @@ fn traverse(grid: &mut SGrid<char>, pos: Pos, dir: Dir, seen: &mut HashSet<(Pos,
     let tile = grid.at(pos);
     match tile {
         '|' if dir.horizontal() => {
+            event!(
+                Level::Debug,
+                "beam split",
+                row = pos.0,
+                col = pos.1,
+                dir = dir
+            );
             if let Some(next) = pos.go_in(Dir::Up, grid) {
                 traverse(grid, next, Dir::Up, seen);
             }
@@ -87,6 +97,13 @@ fn traverse(grid: &mut SGrid<char>, pos: Pos, dir: Dir, seen: &mut HashSet<(Pos,
             }
         }
         '-' if dir.vertical() => {
+            event!(
+                Level::Debug,
+                "beam split",
+                row = pos.0,
+                col = pos.1,
+                dir = dir
+            );
             if let Some(next) = pos.go_in(Dir::Left, grid) {
                 traverse(grid, next, Dir::Left, seen);
             }
@@ -798,8 +815,14 @@ pub fn part2_popcnt_parallel(input: &str) -> i64 {
         .unwrap()
 }
 
+/// A random `size` by `size` contraption, mostly empty space.
+pub fn generate(size: usize, rng: &mut StdRng) -> String {
+    let weights = [('.', 90), ('/', 2), ('\\', 2), ('|', 3), ('-', 3)];
+    gen::grid_to_string(&gen::grid(rng, size, size, &weights))
+}
+
 pub fn main(bench: bool) {
-    let input = std::fs::read_to_string(INPUT_FILE).unwrap();
+    let input = utils::input::read(INPUT_FILE);
 
     let iters = 10;
 
@@ -849,13 +872,13 @@ fn test_part2_example() {
 
 #[test]
 fn test_part1_facit() {
-    let input = std::fs::read_to_string(INPUT_FILE).unwrap();
+    let input = utils::input::read(INPUT_FILE);
     assert_eq!(part1(&input), 7034);
 }
 
 #[test]
 fn test_part2_facit() {
-    let input = std::fs::read_to_string(INPUT_FILE).unwrap();
+    let input = utils::input::read(INPUT_FILE);
     assert_eq!(part2(&input), 7759);
     assert_eq!(part2_opt(&input), 7759);
     assert_eq!(part2_lincache(&input), 7759);
@@ -1,9 +1,6 @@
-use std::{
-    cmp::Reverse,
-    collections::{BinaryHeap, HashSet},
-};
-
-use utils::{Dir, Grid, Pos, PosUtils, Vec2dUtils};
+use itertools::Itertools;
+use rand::rngs::StdRng;
+use utils::{event, gen, search::Search, trace::Level, Dir, Grid, Pos, PosUtils, Vec2dUtils};
 
 static INPUT_FILE: &str = "input/day17";
 #[allow(dead_code)]
@@ -21,104 +18,145 @@ static EXAMPLE_INPUT: &str = r#"2413432311323
 2546548887735
 4322674655533"#;
 
-pub fn part1(input: &str) -> i64 {
-    let grid: Grid<u8> = input
+fn parse(input: &str) -> Grid<u8> {
+    input
         .lines()
-        .map(|l| l.as_bytes().into_iter().map(|c| c - b'0').collect())
-        .collect();
-
-    let h = grid.len();
-    let w = grid[0].len();
-    let final_pos = (h - 1, w - 1);
-
-    let mut seen = HashSet::<(Pos, Dir, usize)>::new();
-    let mut q = BinaryHeap::<Reverse<(i64, Pos, Dir, usize)>>::new();
-    q.push(Reverse((0, (0, 0), Dir::Right, 0)));
-    q.push(Reverse((0, (0, 0), Dir::Down, 0)));
-
-    while q.len() > 0 {
-        let Reverse((loss, pos, dir, cons)) = q.pop().unwrap();
-
-        if pos == final_pos {
-            return loss;
-        }
+        .map(|l| l.as_bytes().iter().map(|c| c - b'0').collect())
+        .collect()
+}
 
-        if !seen.insert((pos, dir, cons)) {
-            continue;
-        }
+/// Where the crucible is, which way it's going, and how many blocks it's gone that way for.
+type State = (Pos, Dir, usize);
 
-        // first, try moving ahead.
-        if cons <= 2 {
-            if let Some(m) = pos.go_in(dir, &grid) {
-                q.push(Reverse((loss + grid.at(m) as i64, m, dir, cons + 1)));
-            }
-        }
-        if let Some(m) = pos.go_in(dir.turn_left(), &grid) {
-            q.push(Reverse((loss + grid.at(m) as i64, m, dir.turn_left(), 1)));
-        }
-        if let Some(m) = pos.go_in(dir.turn_right(), &grid) {
-            q.push(Reverse((loss + grid.at(m) as i64, m, dir.turn_right(), 1)));
-        }
+/// Everywhere the crucible can go from `state` in one block, with the heat lost getting there.
+/// `loss` is how much heat it's lost getting to `state`.
+fn moves(
+    grid: &Grid<u8>,
+    &(pos, dir, run): &State,
+    loss: u64,
+    crucible: Crucible,
+) -> Vec<(State, u64)> {
+    event!(
+        Level::Trace,
+        "node expanded",
+        row = pos.0,
+        col = pos.1,
+        dir = dir,
+        run = run,
+        loss = loss
+    );
+    let mut moves = vec![];
+    if run < crucible.max_run {
+        moves.push((dir, run + 1));
     }
-
-    seen.into_iter()
-        .filter(|v| v.0 == final_pos)
-        .map(|v| v.1)
-        .min()
-        .unwrap() as i64
+    if run >= crucible.min_run {
+        moves.push((dir.turn_left(), 1));
+        moves.push((dir.turn_right(), 1));
+    }
+    moves
+        .into_iter()
+        .filter_map(|(dir, run)| {
+            let next = pos.go_in(dir, grid)?;
+            Some(((next, dir, run), grid.at(next) as u64))
+        })
+        .collect()
 }
 
-pub fn part2(input: &str) -> i64 {
-    let grid: Grid<u8> = input
-        .lines()
-        .map(|l| l.as_bytes().into_iter().map(|c| c - b'0').collect())
-        .collect();
+/// How far a crucible has to go straight before it can turn or stop, and how far it can go
+/// before it has to turn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Crucible {
+    pub min_run: usize,
+    pub max_run: usize,
+}
 
-    let h = grid.len();
-    let w = grid[0].len();
-    let final_pos = (h - 1, w - 1);
+pub const CRUCIBLE: Crucible = Crucible {
+    min_run: 1,
+    max_run: 3,
+};
+pub const ULTRA_CRUCIBLE: Crucible = Crucible {
+    min_run: 4,
+    max_run: 10,
+};
 
-    let mut seen = HashSet::<(Pos, Dir, usize)>::new();
-    let mut q = BinaryHeap::<Reverse<(i64, Pos, Dir, usize)>>::new();
-    q.push(Reverse((0, (0, 0), Dir::Right, 0)));
-    q.push(Reverse((0, (0, 0), Dir::Down, 0)));
+/// The best way from the top left to the bottom right.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Route {
+    pub heat_loss: i64,
+    /// Every block entered after the start, and the direction it was entered going.
+    pub steps: Vec<(Pos, Dir)>,
+}
 
-    while q.len() > 0 {
-        let Reverse((loss, pos, dir, cons)) = q.pop().unwrap();
+/// `None` if the crucible can't get to the bottom right at all, like when it can't go far
+/// enough straight to get there.
+pub fn route(input: &str, crucible: Crucible) -> Option<Route> {
+    let grid = parse(input);
+    let final_pos = (grid.len() - 1, grid[0].len() - 1);
+    let found = Search::from([((0, 0), Dir::Right, 0), ((0, 0), Dir::Down, 0)])
+        .keep_paths()
+        .buckets(
+            |state, loss| moves(&grid, state, loss, crucible),
+            |(pos, _, run)| *pos == final_pos && *run >= crucible.min_run,
+        );
+    let steps = found.path()?[1..]
+        .iter()
+        .map(|(pos, dir, _)| (*pos, *dir))
+        .collect();
+    Some(Route {
+        heat_loss: found.cost()? as i64,
+        steps,
+    })
+}
 
-        if pos == final_pos {
-            if cons >= 4 {
-                return loss;
-            }
-        }
+pub fn part1(input: &str) -> i64 {
+    route(input, CRUCIBLE).expect("no way to the end").heat_loss
+}
 
-        if !seen.insert((pos, dir, cons)) {
-            continue;
-        }
+pub fn part2(input: &str) -> i64 {
+    route(input, ULTRA_CRUCIBLE)
+        .expect("no way to the end")
+        .heat_loss
+}
 
-        // first, try moving ahead.
-        if cons < 10 {
-            if let Some(m) = pos.go_in(dir, &grid) {
-                q.push(Reverse((loss + grid.at(m) as i64, m, dir, cons + 1)));
-            }
-        }
-        // only if we've got enough consecutive movements should we be allowed to turn same
-        // direction
-        if cons >= 4 {
-            if let Some(m) = pos.go_in(dir.turn_left(), &grid) {
-                q.push(Reverse((loss + grid.at(m) as i64, m, dir.turn_left(), 1)));
-            }
-            if let Some(m) = pos.go_in(dir.turn_right(), &grid) {
-                q.push(Reverse((loss + grid.at(m) as i64, m, dir.turn_right(), 1)));
-            }
-        }
+/// The heat loss map with the route drawn over it, like in the puzzle text.
+pub fn render_route(input: &str, route: &Route) -> String {
+    let mut grid = input.lines().map(|l| l.chars().collect_vec()).collect_vec();
+    for ((row, col), dir) in &route.steps {
+        grid[*row][*col] = match dir {
+            Dir::Up => '^',
+            Dir::Right => '>',
+            Dir::Down => 'v',
+            Dir::Left => '<',
+        };
     }
+    grid.iter()
+        .map(|line| line.iter().collect::<String>())
+        .join("\n")
+}
 
-    panic!("can this happen?")
+/// The routes for both parts, for `--route`.
+pub fn render(input: &str) -> String {
+    [("part1", CRUCIBLE), ("part2", ULTRA_CRUCIBLE)]
+        .into_iter()
+        .map(|(name, crucible)| match route(input, crucible) {
+            Some(route) => format!(
+                "{name}: heat loss {}\n{}\n",
+                route.heat_loss,
+                render_route(input, &route)
+            ),
+            None => format!("{name}: no way to the end\n"),
+        })
+        .join("\n")
+}
+
+/// A random `size` by `size` map of heat loss digits.
+pub fn generate(size: usize, rng: &mut StdRng) -> String {
+    let weights = ('1'..='9').map(|c| (c, 1)).collect_vec();
+    gen::grid_to_string(&gen::grid(rng, size, size, &weights))
 }
 
 pub fn main(bench: bool) {
-    let input = std::fs::read_to_string(INPUT_FILE).unwrap();
+    let input = utils::input::read(INPUT_FILE);
 
     let iters = 10;
 
@@ -156,14 +194,86 @@ fn test_part2_example() {
     assert_eq!(part2(EXAMPLE_INPUT), 94);
 }
 
+#[test]
+fn test_route_example() {
+    let best = route(EXAMPLE_INPUT, CRUCIBLE).unwrap();
+    assert_eq!(best.heat_loss, 102);
+    assert_eq!(
+        render_route(EXAMPLE_INPUT, &best),
+        r#"2>>34^>>>1323
+32v>>>35v5623
+32552456v>>54
+3446585845v52
+4546657867v>6
+14385987984v4
+44578769877v6
+36378779796v>
+465496798688v
+456467998645v
+12246868655<v
+25465488877v5
+43226746555v>"#
+    );
+
+    let best = route(EXAMPLE_INPUT, ULTRA_CRUCIBLE).unwrap();
+    assert_eq!(best.heat_loss, 94);
+    assert_eq!(
+        render_route(EXAMPLE_INPUT, &best),
+        r#"2>>>>>>>>1323
+32154535v5623
+32552456v4254
+34465858v5452
+45466578v>>>>
+143859879845v
+445787698776v
+363787797965v
+465496798688v
+456467998645v
+122468686556v
+254654888773v
+432267465553v"#
+    );
+}
+
+#[test]
+fn test_routes_are_valid() {
+    let mut rng = rand::SeedableRng::seed_from_u64(17);
+    let inputs = [
+        EXAMPLE_INPUT.to_string(),
+        generate(30, &mut rng),
+        "111111111111\n999999999991\n999999999991\n999999999991\n999999999991".to_string(),
+    ];
+    for input in &inputs {
+        let grid = parse(input);
+        for crucible in [CRUCIBLE, ULTRA_CRUCIBLE] {
+            let best = route(input, crucible).unwrap();
+            let mut pos = (0, 0);
+            let mut loss = 0;
+            for (next, dir) in &best.steps {
+                assert_eq!(pos.go_in(*dir, &grid), Some(*next));
+                loss += grid.at(*next) as i64;
+                pos = *next;
+            }
+            let runs = best.steps.iter().group_by(|(_, dir)| *dir);
+            assert!(runs
+                .into_iter()
+                .all(|(_, run)| (crucible.min_run..=crucible.max_run).contains(&run.count())));
+            assert_eq!(pos, (grid.len() - 1, grid[0].len() - 1));
+            assert_eq!(loss, best.heat_loss);
+        }
+    }
+    // the ultra crucible can't go the two blocks to the end without stopping short
+    assert_eq!(route("12\n34", ULTRA_CRUCIBLE), None);
+}
+
 #[test]
 fn test_part1_facit() {
-    let input = std::fs::read_to_string(INPUT_FILE).unwrap();
+    let input = utils::input::read(INPUT_FILE);
     assert_eq!(part1(&input), 886);
 }
 
 #[test]
 fn test_part2_facit() {
-    let input = std::fs::read_to_string(INPUT_FILE).unwrap();
+    let input = utils::input::read(INPUT_FILE);
     assert_eq!(part2(&input), 1055);
 }
@@ -1,4 +1,6 @@
-use utils::Dir;
+use itertools::Itertools;
+use rand::{rngs::StdRng, Rng};
+use utils::{gen, Dir, Pos};
 
 static INPUT_FILE: &str = "input/day18";
 #[allow(dead_code)]
@@ -96,8 +98,57 @@ pub fn part2(input: &str) -> i64 {
     1 + (area + perimeter) / 2
 }
 
+/// A random dig plan around a `size` by `size` lattice, going clockwise. Part 1 and part 2 dig
+/// out the same shape, just stretched by different amounts.
+pub fn generate(size: usize, rng: &mut StdRng) -> String {
+    let size = size.max(2);
+    let points = gen::random_loop(rng, size, size);
+    // how far along each axis every lattice line ends up, for both parts
+    let mut stretch = |max_gap: i64| {
+        let mut axis = || {
+            let mut sum = 0;
+            (0..size)
+                .map(|_| {
+                    sum += rng.gen_range(1..=max_gap);
+                    sum
+                })
+                .collect_vec()
+        };
+        (axis(), axis())
+    };
+    let short = stretch(3);
+    let long = stretch((0xfffff / size as i64).max(1));
+    let len = |(rows, cols): &(Vec<i64>, Vec<i64>), a: Pos, b: Pos| {
+        (rows[a.0] - rows[b.0]).abs() + (cols[a.1] - cols[b.1]).abs()
+    };
+
+    let mut lines = vec![];
+    let mut pos = points[0];
+    for (dir, n) in gen::loop_segments(&points) {
+        let next = match dir {
+            Dir::Up => (pos.0 - n, pos.1),
+            Dir::Down => (pos.0 + n, pos.1),
+            Dir::Left => (pos.0, pos.1 - n),
+            Dir::Right => (pos.0, pos.1 + n),
+        };
+        let (c, code) = match dir {
+            Dir::Right => ('R', 0),
+            Dir::Down => ('D', 1),
+            Dir::Left => ('L', 2),
+            Dir::Up => ('U', 3),
+        };
+        lines.push(format!(
+            "{c} {} (#{:05x}{code})",
+            len(&short, pos, next),
+            len(&long, pos, next)
+        ));
+        pos = next;
+    }
+    lines.join("\n")
+}
+
 pub fn main(bench: bool) {
-    let input = std::fs::read_to_string(INPUT_FILE).unwrap();
+    let input = utils::input::read(INPUT_FILE);
 
     let iters = 10;
 
@@ -137,12 +188,52 @@ fn test_part2_example() {
 
 #[test]
 fn test_part1_facit() {
-    let input = std::fs::read_to_string(INPUT_FILE).unwrap();
+    let input = utils::input::read(INPUT_FILE);
     assert_eq!(part1(&input), 33491);
 }
 
 #[test]
 fn test_part2_facit() {
-    let input = std::fs::read_to_string(INPUT_FILE).unwrap();
+    let input = utils::input::read(INPUT_FILE);
     assert_eq!(part2(&input), 87716969654406);
 }
+
+#[test]
+fn test_generate() {
+    use std::collections::HashSet;
+
+    let mut rng = rand::SeedableRng::seed_from_u64(7);
+    let input = generate(12, &mut rng);
+    // dig it out tile by tile and flood the outside instead
+    let mut trench = HashSet::from([(0i64, 0i64)]);
+    let mut pos = (0, 0);
+    for line in input.lines() {
+        let mut l = line.split_whitespace();
+        let dir = Dir::from_char(l.next().unwrap().chars().next().unwrap());
+        for _ in 0..l.next().unwrap().parse().unwrap() {
+            pos = match dir {
+                Dir::Up => (pos.0 - 1, pos.1),
+                Dir::Down => (pos.0 + 1, pos.1),
+                Dir::Left => (pos.0, pos.1 - 1),
+                Dir::Right => (pos.0, pos.1 + 1),
+            };
+            trench.insert(pos);
+        }
+    }
+    let (min, max) = (
+        trench.iter().map(|p| p.0.min(p.1)).min().unwrap() - 1,
+        trench.iter().map(|p| p.0.max(p.1)).max().unwrap() + 1,
+    );
+    let mut outside = HashSet::from([(min, min)]);
+    let mut stack = vec![(min, min)];
+    while let Some((r, c)) = stack.pop() {
+        for next in [(r - 1, c), (r + 1, c), (r, c - 1), (r, c + 1)] {
+            let in_bounds = [next.0, next.1].iter().all(|v| (min..=max).contains(v));
+            if in_bounds && !trench.contains(&next) && outside.insert(next) {
+                stack.push(next);
+            }
+        }
+    }
+    let total = (max - min + 1) * (max - min + 1);
+    assert_eq!(part1(&input), total - outside.len() as i64);
+}
@@ -1,5 +1,12 @@
-use std::{collections::HashMap, ops::RangeInclusive};
-use utils::range_intersect;
+use std::collections::{HashMap, VecDeque};
+
+use itertools::Itertools;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng};
+use utils::{
+    dot::{Dot, HIGHLIGHT},
+    gen,
+    range_set::RangeSet,
+};
 
 static INPUT_FILE: &str = "input/day19";
 #[allow(dead_code)]
@@ -166,20 +173,12 @@ pub fn part2<'a>(input: &'a str) -> i64 {
         }
     }
 
-    type PartRange = HashMap<char, RangeInclusive<i64>>;
-    let part = PartRange::from([
-        ('x', 1..=4000),
-        ('m', 1..=4000),
-        ('a', 1..=4000),
-        ('s', 1..=4000),
-    ]);
+    type PartRange = HashMap<char, RangeSet<i64>>;
+    let part = PartRange::from(['x', 'm', 'a', 's'].map(|c| (c, RangeSet::from(1..=4000))));
 
     fn evaluate(part: PartRange, rule: &[Instr], rules: &HashMap<&str, Vec<Instr>>) -> i64 {
         match rule.first().unwrap() {
-            Instr::Accept => part
-                .values()
-                .map(|r| r.end() - r.start() + 1)
-                .product::<i64>(),
+            Instr::Accept => part.values().map(|r| r.len() as i64).product::<i64>(),
             Instr::Reject => {
                 return 0;
             }
@@ -195,25 +194,20 @@ pub fn part2<'a>(input: &'a str) -> i64 {
             } => {
                 let cv = part.get(component).unwrap();
                 let (matching, non_matching) = if *gt {
-                    (
-                        range_intersect(cv, &((num + 1)..=i64::MAX)),
-                        range_intersect(cv, &(i64::MIN..=(*num))),
-                    )
+                    let (below, above) = cv.split_at(num + 1);
+                    (above, below)
                 } else {
-                    (
-                        range_intersect(cv, &(i64::MIN..=(num - 1))),
-                        range_intersect(cv, &(*num..=i64::MAX)),
-                    )
+                    cv.split_at(*num)
                 };
                 let mut result = 0;
-                if let Some(r) = matching {
+                if !matching.is_empty() {
                     let mut new_part = part.clone();
-                    new_part.insert(*component, r);
+                    new_part.insert(*component, matching);
                     result += evaluate(new_part, &vec![then.as_ref().clone()], rules);
                 }
-                if let Some(r) = non_matching {
+                if !non_matching.is_empty() {
                     let mut new_part = part.clone();
-                    new_part.insert(*component, r);
+                    new_part.insert(*component, non_matching);
                     result += evaluate(new_part, &rule[1..], rules);
                 }
                 result
@@ -224,8 +218,62 @@ pub fn part2<'a>(input: &'a str) -> i64 {
     evaluate(part, rules.get("in").unwrap(), &rules)
 }
 
+/// The workflows as a DOT digraph, one edge per rule labeled with its condition. Branches that
+/// accept a part are highlighted.
+pub fn dot(input: &str) -> String {
+    let mut dot = Dot::digraph("day19");
+    dot.node("in", &[("shape", "doublecircle")])
+        .node("A", &[("shape", "box"), ("color", "darkgreen")])
+        .node("R", &[("shape", "box")]);
+    for line in input.lines().take_while(|l| !l.is_empty()) {
+        let (id, tail) = line.split_once('{').unwrap();
+        for instr in tail[0..tail.len() - 1].split(',') {
+            let (label, then) = instr.split_once(':').unwrap_or(("", instr));
+            let mut attrs = vec![("label", label)];
+            if then == "A" {
+                attrs.extend_from_slice(HIGHLIGHT);
+            }
+            dot.edge(id, then, &attrs);
+        }
+    }
+    dot.to_string()
+}
+
+/// A random tree of `size` workflows hanging off of `in`, plus 200 random parts.
+pub fn generate(size: usize, rng: &mut StdRng) -> String {
+    let mut names = gen::distinct_words(rng, size.max(1) - 1, 3, gen::LOWERCASE, &[]).into_iter();
+    let mut queue = VecDeque::from(["in".to_string()]);
+    let mut workflows = vec![];
+    while let Some(name) = queue.pop_front() {
+        let mut target = |rng: &mut StdRng| match names.len() {
+            n if n > 0 && rng.gen_range(0..10) < 6 => {
+                let next = names.next().unwrap();
+                queue.push_back(next.clone());
+                next
+            }
+            _ => if rng.gen() { "A" } else { "R" }.to_string(),
+        };
+        let mut rules = (0..rng.gen_range(1..=3))
+            .map(|_| {
+                let rating = ['x', 'm', 'a', 's'][rng.gen_range(0..4)];
+                let op = if rng.gen() { '<' } else { '>' };
+                let value = rng.gen_range(2..4000);
+                format!("{rating}{op}{value}:{}", target(rng))
+            })
+            .collect_vec();
+        rules.push(target(rng));
+        workflows.push(format!("{name}{{{}}}", rules.join(",")));
+    }
+    workflows.shuffle(rng);
+    let parts = (0..200).map(|_| {
+        let [x, m, a, s] = [(); 4].map(|_| rng.gen_range(1..=4000));
+        format!("{{x={x},m={m},a={a},s={s}}}")
+    });
+    format!("{}\n\n{}", workflows.join("\n"), parts.format("\n"))
+}
+
 pub fn main(bench: bool) {
-    let input = std::fs::read_to_string(INPUT_FILE).unwrap();
+    let input = utils::input::read(INPUT_FILE);
 
     let iters = 10;
 
@@ -266,14 +314,22 @@ fn test_part2_example() {
     assert_eq!(part2(EXAMPLE_INPUT), 167409079868000);
 }
 
+#[test]
+fn test_dot() {
+    let dot = dot(EXAMPLE_INPUT);
+    assert!(dot.contains(r#""in" -> "px" [label="s<1351"];"#));
+    assert!(dot.contains(r#""in" -> "qqz" [label=""];"#));
+    assert!(dot.contains(r#""lnx" -> "A" [label="m>1548", color="red", penwidth="3"];"#));
+}
+
 #[test]
 fn test_part1_facit() {
-    let input = std::fs::read_to_string(INPUT_FILE).unwrap();
+    let input = utils::input::read(INPUT_FILE);
     assert_eq!(part1(&input), 480738);
 }
 
 #[test]
 fn test_part2_facit() {
-    let input = std::fs::read_to_string(INPUT_FILE).unwrap();
+    let input = utils::input::read(INPUT_FILE);
     assert_eq!(part2(&input), 131550418841958);
 }
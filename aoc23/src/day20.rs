@@ -1,207 +1,640 @@
-use std::collections::{HashMap, HashSet, VecDeque};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    ops::Range,
+};
 
 use itertools::Itertools;
 use num::integer::lcm;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng};
+use utils::{
+    assume::{self, Assumption},
+    dot::{Dot, HIGHLIGHT},
+    event, gen,
+    trace::Level,
+    vcd::Vcd,
+};
 
 static INPUT_FILE: &str = "input/day20";
 
-pub fn part1(input: &str) -> i64 {
-    let mut flip_flops = HashMap::<&str, (bool, Vec<&str>)>::new();
-    let mut conjunctions = HashMap::<&str, (Vec<(&str, bool)>, Vec<&str>)>::new();
-    for mut line in input.lines() {
-        let t = line.chars().next().unwrap();
-        if !line.starts_with("broadcaster") {
-            line = &line[1..];
+/// Index of a module in a [`Circuit`].
+pub type ModuleId = usize;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Module {
+    /// Sends a single low pulse to the broadcaster per press.
+    Button,
+    Broadcaster,
+    /// Flip-flop modules (prefix %) are either on or off; they are initially off. If a flip-flop
+    /// module receives a high pulse, it is ignored and nothing happens. However, if a flip-flop
+    /// module receives a low pulse, it flips between on and off. If it was off, it turns on and
+    /// sends a high pulse. If it was on, it turns off and sends a low pulse.
+    FlipFlop {
+        on: bool,
+    },
+    /// Conjunction modules (prefix &) remember the type of the most recent pulse received from
+    /// each of their connected input modules; they initially default to remembering a low pulse
+    /// for each input. When a pulse is received, the conjunction module first updates its memory
+    /// for that input. Then, if it remembers high pulses for all inputs, it sends a low pulse;
+    /// otherwise, it sends a high pulse.
+    ///
+    /// `memory` lines up with the module's entry in [`Circuit::inputs`].
+    Conjunction {
+        memory: Vec<bool>,
+    },
+    /// Something that's only ever sent to, like `rx`.
+    Output,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pulse {
+    /// How many pulses led up to this one within its press, starting with 0 for the button's.
+    pub step: usize,
+    pub from: ModuleId,
+    pub to: ModuleId,
+    pub high: bool,
+}
+
+/// The module network, with every name interned to a [`ModuleId`].
+#[derive(Debug, Clone)]
+pub struct Circuit<'a> {
+    pub names: Vec<&'a str>,
+    pub modules: Vec<Module>,
+    pub outputs: Vec<Vec<ModuleId>>,
+    pub inputs: Vec<Vec<ModuleId>>,
+    pub button: ModuleId,
+    pub presses: usize,
+    ids: HashMap<&'a str, ModuleId>,
+}
+
+impl<'a> Circuit<'a> {
+    /// Modules get ids in the order they're declared, then the ones that are only sent to, then
+    /// the button.
+    pub fn parse(input: &'a str) -> Self {
+        let declared = input
+            .lines()
+            .map(|line| {
+                let (from, targets) = line.split_once(" -> ").unwrap();
+                let module = match from.chars().next().unwrap() {
+                    '%' => Module::FlipFlop { on: false },
+                    '&' => Module::Conjunction { memory: vec![] },
+                    _ if from == "broadcaster" => Module::Broadcaster,
+                    _ => panic!("unknown module: {from}"),
+                };
+                (from.trim_start_matches(['%', '&']), module, targets)
+            })
+            .collect_vec();
+        let mut circuit = Circuit {
+            names: vec![],
+            modules: vec![],
+            outputs: vec![],
+            inputs: vec![],
+            button: 0,
+            presses: 0,
+            ids: HashMap::new(),
+        };
+        for (name, module, _) in &declared {
+            circuit.intern(name, module.clone());
+        }
+        for (name, _, targets) in &declared {
+            let from = circuit.ids[name];
+            for target in targets.split(", ") {
+                let to = circuit.intern(target, Module::Output);
+                circuit.outputs[from].push(to);
+                circuit.inputs[to].push(from);
+            }
+        }
+        circuit.button = circuit.intern("button", Module::Button);
+        let broadcaster = *circuit
+            .ids
+            .get("broadcaster")
+            .expect("there's no broadcaster");
+        circuit.outputs[circuit.button].push(broadcaster);
+        circuit.inputs[broadcaster].push(circuit.button);
+        for (id, module) in circuit.modules.iter_mut().enumerate() {
+            if let Module::Conjunction { memory } = module {
+                *memory = vec![false; circuit.inputs[id].len()];
+            }
         }
-        let (from, targets) = line.split_once(" -> ").unwrap();
-        let targets = targets.split(", ").collect_vec();
-        if t == '&' {
-            conjunctions.insert(from, (vec![], targets));
-        } else {
-            flip_flops.insert(from, (false, targets));
+        circuit
+    }
+
+    fn intern(&mut self, name: &'a str, module: Module) -> ModuleId {
+        *self.ids.entry(name).or_insert_with(|| {
+            self.names.push(name);
+            self.modules.push(module);
+            self.outputs.push(vec![]);
+            self.inputs.push(vec![]);
+            self.names.len() - 1
+        })
+    }
+
+    pub fn id(&self, name: &str) -> Option<ModuleId> {
+        self.ids.get(name).copied()
+    }
+
+    /// The name as it's declared, like `%a` or `&inv`.
+    pub fn label(&self, id: ModuleId) -> String {
+        match self.modules[id] {
+            Module::FlipFlop { .. } => format!("%{}", self.names[id]),
+            Module::Conjunction { .. } => format!("&{}", self.names[id]),
+            _ => self.names[id].to_string(),
         }
     }
-    let mut deps: Vec<(&str, &str)> = vec![];
-    // add conjunction backrefs
-    for (from, flip) in flip_flops.iter() {
-        for target in flip.1.iter() {
-            deps.push((from, target));
+
+    pub fn labels(&self, ids: &[ModuleId]) -> String {
+        ids.iter().map(|id| self.label(*id)).join(", ")
+    }
+
+    /// Presses the button and delivers every pulse that follows, in order, calling `on_pulse`
+    /// with each as it's delivered.
+    pub fn press(&mut self, mut on_pulse: impl FnMut(&Pulse)) {
+        self.presses += 1;
+        let mut q = VecDeque::from([Pulse {
+            step: 0,
+            from: self.button,
+            to: self.outputs[self.button][0],
+            high: false,
+        }]);
+        while let Some(pulse) = q.pop_front() {
+            event!(
+                Level::Debug,
+                "pulse",
+                from = self.names[pulse.from],
+                to = self.names[pulse.to],
+                high = pulse.high
+            );
+            on_pulse(&pulse);
+            let output = match &mut self.modules[pulse.to] {
+                Module::Broadcaster => pulse.high,
+                Module::FlipFlop { .. } if pulse.high => continue,
+                Module::FlipFlop { on } => {
+                    *on ^= true;
+                    *on
+                }
+                Module::Conjunction { memory } => {
+                    for (from, remembered) in self.inputs[pulse.to].iter().zip(memory.iter_mut()) {
+                        if *from == pulse.from {
+                            *remembered = pulse.high;
+                        }
+                    }
+                    !memory.iter().all(|v| *v)
+                }
+                Module::Button | Module::Output => continue,
+            };
+            for to in &self.outputs[pulse.to] {
+                q.push_back(Pulse {
+                    step: pulse.step + 1,
+                    from: pulse.to,
+                    to: *to,
+                    high: output,
+                });
+            }
         }
     }
-    for (from, flip) in conjunctions.iter() {
-        for target in flip.1.iter() {
-            deps.push((from, target));
+
+    /// Presses the button, returning every pulse it led to.
+    pub fn press_logged(&mut self) -> Vec<Pulse> {
+        let mut log = vec![];
+        self.press(|pulse| log.push(*pulse));
+        log
+    }
+
+    /// Everything remembered by the modules in `of`, which is all that decides what they do next
+    /// if nothing outside them sends them anything but the broadcaster.
+    pub fn state(&self, of: &[ModuleId]) -> Vec<bool> {
+        let mut state = vec![];
+        for id in of {
+            match &self.modules[*id] {
+                Module::FlipFlop { on } => state.push(*on),
+                Module::Conjunction { memory } => state.extend(memory),
+                _ => {}
+            }
         }
+        state
     }
-    for (src, dest) in deps {
-        if let Some(v) = conjunctions.get_mut(dest) {
-            v.0.push((src, false));
+
+    /// Every module that can get a pulse to `id` without going through the broadcaster (or the
+    /// button), sorted, with `id` itself.
+    pub fn upstream(&self, id: ModuleId) -> Vec<ModuleId> {
+        let mut seen = HashSet::from([id]);
+        let mut stack = vec![id];
+        while let Some(id) = stack.pop() {
+            for from in &self.inputs[id] {
+                if matches!(
+                    self.modules[*from],
+                    Module::FlipFlop { .. } | Module::Conjunction { .. }
+                ) && seen.insert(*from)
+                {
+                    stack.push(*from);
+                }
+            }
         }
+        seen.into_iter().sorted().collect()
     }
+}
 
+pub fn part1(input: &str) -> i64 {
+    let mut circuit = Circuit::parse(input);
     let mut lows = 0;
     let mut highs = 0;
     for _ in 0..1000 {
-        let mut q = VecDeque::<(&str, bool, &str)>::new();
-        q.push_back(("broadcaster", false, "button"));
-        while let Some((id, sig, sender)) = q.pop_front() {
-            if cfg!(test) {
-                println!("{sender} -{}-> {id}", if sig { "high" } else { "low" });
-            }
-            if sig {
+        circuit.press(|pulse| {
+            if pulse.high {
                 highs += 1;
             } else {
                 lows += 1;
             }
-            if let Some(flip_flop) = flip_flops.get_mut(id) {
-                if id == "broadcaster" {
-                    for target in flip_flop.1.iter() {
-                        q.push_back((target, sig, id));
-                    }
-                } else {
-                    // Flip-flop modules (prefix %) are either on or off; they are initially off. If a
-                    // flip-flop module receives a high pulse, it is ignored and nothing happens. However,
-                    // if a flip-flop module receives a low pulse, it flips between on and off. If it was
-                    // off, it turns on and sends a high pulse. If it was on, it turns off and sends a low
-                    // pulse.
-                    if !sig {
-                        flip_flop.0 ^= true;
-                        for target in flip_flop.1.iter() {
-                            q.push_back((target, flip_flop.0, id));
-                        }
-                    }
-                }
-            } else if let Some(conjunction) = conjunctions.get_mut(id) {
-                // Conjunction modules (prefix &) remember the type of the most recent pulse
-                // received from each of their connected input modules; they initially default to
-                // remembering a low pulse for each input. When a pulse is received, the
-                // conjunction module first updates its memory for that input. Then, if it
-                // remembers high pulses for all inputs, it sends a low pulse; otherwise, it sends
-                // a high pulse.
-                for input in conjunction.0.iter_mut() {
-                    if input.0 == sender {
-                        input.1 = sig;
-                    }
-                }
-                let output = !conjunction.0.iter().all(|v| v.1);
-                for target in conjunction.1.iter() {
-                    q.push_back((target, output, id));
-                }
-            }
-        }
+        });
     }
-
     highs * lows
 }
 
 pub fn part2(input: &str) -> i64 {
-    let mut flip_flops = HashMap::<&str, (bool, Vec<&str>)>::new();
-    let mut conjunctions = HashMap::<&str, (Vec<(&str, bool)>, Vec<&str>)>::new();
-    for mut line in input.lines() {
-        let t = line.chars().next().unwrap();
-        if !line.starts_with("broadcaster") {
-            line = &line[1..];
-        }
-        let (from, targets) = line.split_once(" -> ").unwrap();
-        let targets = targets.split(", ").collect_vec();
-        if t == '&' {
-            conjunctions.insert(from, (vec![], targets));
-        } else {
-            flip_flops.insert(from, (false, targets));
+    match rx_subcircuits(input) {
+        Ok(subcircuits) => subcircuits.iter().map(|s| s.period as i64).fold(1, lcm),
+        Err(e) => panic!("{e}"),
+    }
+}
+
+/// Presses the button until `rx` gets a low pulse. Only useful for small circuits.
+pub fn part2_brute(input: &str) -> i64 {
+    let mut circuit = Circuit::parse(input);
+    let rx = circuit.id("rx").expect("there's no rx");
+    loop {
+        let mut done = false;
+        circuit.press(|pulse| done |= pulse.to == rx && !pulse.high);
+        if done {
+            return circuit.presses as i64;
         }
     }
-    let mut deps: Vec<(&str, &str)> = vec![];
-    // add conjunction backrefs
-    for (from, flip) in flip_flops.iter() {
-        for target in flip.1.iter() {
-            deps.push((from, target));
+}
+
+/// One of the independent counters whose outputs all need to be high together for the conjunction
+/// feeding `rx` to send it a low pulse.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubCircuit {
+    /// The module sending to the conjunction feeding `rx`.
+    pub output: ModuleId,
+    /// `output` and everything upstream of it.
+    pub modules: Vec<ModuleId>,
+    /// How many presses it takes to get back to a state it's been in, and it sends a high pulse
+    /// to the conjunction on exactly the presses that are multiples of this.
+    pub period: usize,
+    /// The steps in those presses between that high pulse arriving and the low one following it.
+    pub high: Range<usize>,
+}
+
+/// How many presses to look for the sub-circuits' periods in before giving up.
+const MAX_PERIOD: usize = 1 << 16;
+
+/// Splits the network into the sub-circuits that decide when `rx` gets a low pulse, and finds how
+/// often each of them fires, if the answer to part 2 is the lcm of those periods. That takes:
+///
+/// - `rx` being fed by a single conjunction, which needs a high pulse from each of its inputs.
+/// - The modules upstream of each of those inputs being a separate sub-circuit that only hears
+///   from the broadcaster, so that its state after a press only depends on its state before.
+/// - Each sub-circuit's state looping with some period, and it sending the conjunction a high
+///   pulse (followed by a low one, at the same point in the press every time) on exactly the
+///   presses that are multiples of that period.
+/// - The high pulses from all of them overlapping on the press where they all fire, which only
+///   depends on the shape of the network since each press starts the same way.
+///
+/// Anything else gets an `Err` saying which of those broke.
+pub fn rx_subcircuits(input: &str) -> Result<Vec<SubCircuit>, String> {
+    let mut circuit = Circuit::parse(input);
+    let rx = circuit.id("rx").ok_or("there's no rx")?;
+    let feeder = match circuit.inputs[rx][..] {
+        [feeder] if matches!(circuit.modules[feeder], Module::Conjunction { .. }) => feeder,
+        [] => return Err("nothing sends to rx".to_string()),
+        _ => {
+            return Err(format!(
+                "rx is fed by {}",
+                circuit.labels(&circuit.inputs[rx])
+            ))
         }
+    };
+    let broadcaster = circuit.id("broadcaster").unwrap();
+    if circuit.inputs[broadcaster] != [circuit.button] {
+        return Err(format!(
+            "the broadcaster is fed by {}",
+            circuit.labels(&circuit.inputs[broadcaster][..circuit.inputs[broadcaster].len() - 1])
+        ));
     }
-    for (from, flip) in conjunctions.iter() {
-        for target in flip.1.iter() {
-            deps.push((from, target));
+    let outputs = circuit.inputs[feeder].clone();
+    let cones = outputs.iter().map(|id| circuit.upstream(*id)).collect_vec();
+    for (output, cone) in outputs.iter().zip(&cones) {
+        if cone.contains(&feeder) {
+            return Err(format!(
+                "{} feeds back into {}",
+                circuit.label(feeder),
+                circuit.label(*output)
+            ));
         }
     }
-    for (src, dest) in deps {
-        if let Some(v) = conjunctions.get_mut(dest) {
-            v.0.push((src, false));
+    for ((a, cone_a), (b, cone_b)) in outputs.iter().zip(&cones).tuple_combinations() {
+        if let Some(shared) = cone_a.iter().find(|id| cone_b.contains(id)) {
+            return Err(format!(
+                "the sub-circuits behind {} and {} share {}",
+                circuit.label(*a),
+                circuit.label(*b),
+                circuit.label(*shared)
+            ));
         }
     }
 
-    // this isn't generic i suppose, but then neither is the lcm approach in general? dont like
-    // this day, same as day08, the fact that the lcm approach works is an accident and it doesn't
-    // work without it. allowing myself to hard-code input-dependent stuff bc of this lol.
-    let mut rx_inputs: HashSet<&str> = conjunctions
+    let slot = |id: ModuleId| outputs.iter().position(|o| *o == id);
+    let mut seen = cones
         .iter()
-        .filter(|v| v.1 .1.contains(&"rg"))
-        .map(|v| *v.0)
-        .collect();
-    let mut cycle_lengths: Vec<i64> = vec![];
-    'find_cycles: for ix in 1.. {
-        let mut q = VecDeque::<(&str, bool, &str)>::new();
-        q.push_back(("broadcaster", false, "button"));
-        while let Some((id, sig, sender)) = q.pop_front() {
-            if cfg!(test) {
-                println!("{sender} -{}-> {id}", if sig { "high" } else { "low" });
+        .map(|cone| HashMap::from([(circuit.state(cone), 0)]))
+        .collect_vec();
+    let mut periods = vec![None; outputs.len()];
+    // the steps each output was high to the feeder for, on each press it was
+    let mut highs = vec![vec![]; outputs.len()];
+    while periods.contains(&None) {
+        if circuit.presses == MAX_PERIOD {
+            let stuck = outputs
+                .iter()
+                .zip(&periods)
+                .filter(|(_, p)| p.is_none())
+                .map(|(o, _)| *o)
+                .collect_vec();
+            return Err(format!(
+                "{} don't repeat within {MAX_PERIOD} presses",
+                circuit.labels(&stuck)
+            ));
+        }
+        let mut since = vec![None; outputs.len()];
+        let mut windows = vec![vec![]; outputs.len()];
+        circuit.press(|pulse| {
+            let Some(ix) = slot(pulse.from).filter(|_| pulse.to == feeder) else {
+                return;
+            };
+            match (pulse.high, since[ix]) {
+                (true, None) => since[ix] = Some(pulse.step),
+                (false, Some(start)) => {
+                    windows[ix].push(start..pulse.step);
+                    since[ix] = None;
+                }
+                _ => {}
+            }
+        });
+        let press = circuit.presses;
+        for (ix, cone) in cones.iter().enumerate() {
+            if since[ix].is_some() {
+                return Err(format!(
+                    "{} is still sending {} high at the end of press {press}",
+                    circuit.label(outputs[ix]),
+                    circuit.label(feeder)
+                ));
             }
+            if !windows[ix].is_empty() && periods[ix].is_none() {
+                highs[ix].push((press, std::mem::take(&mut windows[ix])));
+            }
+            if periods[ix].is_some() {
+                continue;
+            }
+            if let Some(start) = seen[ix].insert(circuit.state(cone), press) {
+                periods[ix] = Some((press - start, press));
+            }
+        }
+    }
 
-            if !sig {
-                if rx_inputs.contains(id) {
-                    cycle_lengths.push(ix);
-                    rx_inputs.remove(id);
-                    if rx_inputs.is_empty() {
-                        break 'find_cycles;
-                    }
-                }
+    let mut subcircuits = vec![];
+    for (ix, period) in periods.into_iter().enumerate() {
+        // every press up to the end of the first loop, which covers all it will ever do
+        let ((period, last), label) = (period.unwrap(), circuit.label(outputs[ix]));
+        let presses = highs[ix].iter().map(|(press, _)| *press).collect_vec();
+        if presses.is_empty() {
+            return Err(format!(
+                "{label} never sends {} a high pulse",
+                circuit.label(feeder)
+            ));
+        }
+        if presses != (period..=last).step_by(period).collect_vec() {
+            return Err(format!(
+                "{label} sends {} high on presses {} but repeats every {period}",
+                circuit.label(feeder),
+                presses.iter().join(", ")
+            ));
+        }
+        let high = match highs[ix].iter().map(|(_, w)| &w[..]).all_equal_value() {
+            Ok([window]) => window.clone(),
+            _ => {
+                return Err(format!(
+                    "{label} doesn't send {} a single high pulse at the same point every time",
+                    circuit.label(feeder)
+                ))
             }
+        };
+        event!(
+            Level::Info,
+            "sub-circuit",
+            output = &label[..],
+            period = period
+        );
+        subcircuits.push(SubCircuit {
+            output: outputs[ix],
+            modules: cones[ix].clone(),
+            period,
+            high,
+        });
+    }
+    let start = subcircuits.iter().map(|s| s.high.start).max();
+    let end = subcircuits.iter().map(|s| s.high.end).min();
+    if start >= end {
+        return Err(format!(
+            "the high pulses to {} don't overlap when they all fire",
+            circuit.label(feeder)
+        ));
+    }
+    Ok(subcircuits)
+}
 
-            if let Some(flip_flop) = flip_flops.get_mut(id) {
-                if id == "broadcaster" {
-                    for target in flip_flop.1.iter() {
-                        q.push_back((target, sig, id));
-                    }
-                } else {
-                    // Flip-flop modules (prefix %) are either on or off; they are initially off. If a
-                    // flip-flop module receives a high pulse, it is ignored and nothing happens. However,
-                    // if a flip-flop module receives a low pulse, it flips between on and off. If it was
-                    // off, it turns on and sends a high pulse. If it was on, it turns off and sends a low
-                    // pulse.
-                    if !sig {
-                        flip_flop.0 ^= true;
-                        for target in flip_flop.1.iter() {
-                            q.push_back((target, flip_flop.0, id));
+pub static ASSUMPTIONS: &[Assumption] = &[Assumption {
+    part: 2,
+    name: "lcm_shortcut",
+    description: "rx is fed by one conjunction, whose inputs are independent counters that each \
+        fire once at the end of their period, so rx first gets a low pulse on the lcm of those",
+    check: |input| rx_subcircuits(input).map(|_| ()),
+}];
+
+/// The module network as a DOT digraph. Flip-flops are boxes, conjunctions are diamonds, and the
+/// inputs of whatever feeds `rx` (the ones part2 looks for cycles in) are highlighted.
+pub fn dot(input: &str) -> String {
+    let circuit = Circuit::parse(input);
+    let rx_feeders = circuit
+        .id("rx")
+        .map(|rx| circuit.inputs[rx].clone())
+        .unwrap_or_default();
+
+    let mut dot = Dot::digraph("day20");
+    for (id, name) in circuit.names.iter().enumerate() {
+        let shape = match circuit.modules[id] {
+            Module::FlipFlop { .. } => "box",
+            Module::Conjunction { .. } => "diamond",
+            Module::Broadcaster => "doublecircle",
+            Module::Button | Module::Output => continue,
+        };
+        dot.node(name, &[("shape", shape)]);
+    }
+    for (from, targets) in circuit.outputs.iter().enumerate() {
+        if from == circuit.button {
+            continue;
+        }
+        for to in targets {
+            let attrs = if rx_feeders.contains(to) {
+                HIGHLIGHT
+            } else {
+                &[]
+            };
+            dot.edge(circuit.names[from], circuit.names[*to], attrs);
+        }
+    }
+    dot.to_string()
+}
+
+/// The pulses from `presses` presses of the button as a VCD waveform. Every module gets a scope
+/// with an `out` wire for the pulses it's sending (or `in` for the ones `rx` and the like get)
+/// plus whatever it remembers, `on` for flip-flops and one bit per input in `memory` for
+/// conjunctions, with the first input as the lowest bit. Each step of a press is a tick, and
+/// `press` counts the presses.
+pub fn vcd(input: &str, presses: usize) -> String {
+    let mut circuit = Circuit::parse(input);
+    let mut vcd = Vcd::new("1ns");
+    vcd.scope("day20");
+    let press = vcd.wire("press", 32);
+    let mut wires = vec![];
+    for (id, name) in circuit.names.iter().enumerate() {
+        vcd.scope(name);
+        let state = match &circuit.modules[id] {
+            Module::FlipFlop { .. } => Some(vcd.wire("on", 1)),
+            Module::Conjunction { memory } => Some(vcd.wire("memory", memory.len())),
+            _ => None,
+        };
+        let pulses = match circuit.modules[id] {
+            Module::Output => vcd.wire("in", 1),
+            _ => vcd.wire("out", 1),
+        };
+        vcd.upscope();
+        wires.push((state, pulses));
+    }
+    vcd.upscope();
+
+    vcd.change(press, 0);
+    for (state, pulses) in &wires {
+        if let Some(state) = state {
+            vcd.change(*state, 0);
+        }
+        vcd.change(*pulses, 0);
+    }
+    // what every module remembers, as its wire's value
+    let mut states = vec![0u64; circuit.names.len()];
+    let (modules, inputs) = (circuit.modules.clone(), circuit.inputs.clone());
+    let mut start = 1;
+    for _ in 0..presses {
+        let mut end = start;
+        vcd.time(start).change(press, circuit.presses as u64 + 1);
+        circuit.press(|pulse| {
+            let (to, high) = (pulse.to, pulse.high as u64);
+            end = start + pulse.step as u64;
+            vcd.time(end).change(wires[pulse.from].1, high);
+            match modules[to] {
+                Module::FlipFlop { .. } if high == 0 => states[to] ^= 1,
+                Module::Conjunction { .. } => {
+                    for (bit, from) in inputs[to].iter().enumerate() {
+                        if *from == pulse.from {
+                            states[to] = states[to] & !(1 << bit) | high << bit;
                         }
                     }
                 }
-            } else if let Some(conjunction) = conjunctions.get_mut(id) {
-                // Conjunction modules (prefix &) remember the type of the most recent pulse
-                // received from each of their connected input modules; they initially default to
-                // remembering a low pulse for each input. When a pulse is received, the
-                // conjunction module first updates its memory for that input. Then, if it
-                // remembers high pulses for all inputs, it sends a low pulse; otherwise, it sends
-                // a high pulse.
-                for input in conjunction.0.iter_mut() {
-                    if input.0 == sender {
-                        input.1 = sig;
-                    }
-                }
-                let output = !conjunction.0.iter().all(|v| v.1);
-                for target in conjunction.1.iter() {
-                    q.push_back((target, output, id));
+                Module::Output => {
+                    vcd.change(wires[to].1, high);
                 }
+                _ => {}
+            }
+            if let Some(state) = wires[to].0 {
+                vcd.change(state, states[to]);
             }
+        });
+        start = end + 1;
+    }
+    vcd.to_string()
+}
+
+/// A random circuit of `size` twelve-bit counters that each fire `rg` (and so `rx`) with a
+/// different prime period between 2048 and 4096 presses.
+pub fn generate(size: usize, rng: &mut StdRng) -> String {
+    let size = size.max(1);
+    let is_prime = |n: usize| (2..).take_while(|d| d * d <= n).all(|d| n % d != 0);
+    let mut periods = vec![];
+    while periods.len() < size {
+        let p = rng.gen_range(2049..4096);
+        if is_prime(p) && !periods.contains(&p) {
+            periods.push(p);
         }
     }
+    counters(&periods, 12, rng)
+}
+
+/// A circuit of `bits`-bit counters, one per period, each firing `rg` (and so `rx`) every
+/// `period` presses. Periods have to be odd and fit in `bits` bits.
+fn counters(periods: &[usize], bits: usize, rng: &mut StdRng) -> String {
+    let words = periods.len() * (bits + 2);
+    let mut names = gen::distinct_words(rng, words, 2, gen::LOWERCASE, &["rg", "rx"]).into_iter();
 
-    cycle_lengths.into_iter().fold(1, |a, b| lcm(a, b))
+    let mut lines = vec![];
+    let mut starts = vec![];
+    for &period in periods {
+        let bits = (0..bits).map(|_| names.next().unwrap()).collect_vec();
+        let (counter, inverter) = (names.next().unwrap(), names.next().unwrap());
+        for (ix, bit) in bits.iter().enumerate() {
+            let mut targets = vec![];
+            if let Some(next) = bits.get(ix + 1) {
+                targets.push(next.as_str());
+            }
+            if period & (1 << ix) != 0 {
+                targets.push(&counter);
+            }
+            targets.shuffle(rng);
+            lines.push(format!("%{bit} -> {}", targets.join(", ")));
+        }
+        // once every bit of the period is on, set the rest so the counter overflows back to 0
+        let mut targets = (0..bits.len())
+            .filter(|ix| ix == &0 || period & (1 << ix) == 0)
+            .map(|ix| bits[ix].as_str())
+            .collect_vec();
+        targets.push(&inverter);
+        targets.shuffle(rng);
+        lines.push(format!("&{counter} -> {}", targets.join(", ")));
+        lines.push(format!("&{inverter} -> rg"));
+        starts.push(bits[0].clone());
+    }
+    lines.push(format!("broadcaster -> {}", starts.join(", ")));
+    lines.push("&rg -> rx".to_string());
+    lines.shuffle(rng);
+    lines.join("\n")
 }
 
 pub fn main(bench: bool) {
-    let input = std::fs::read_to_string(INPUT_FILE).unwrap();
+    let input = utils::input::read(INPUT_FILE);
 
     let iters = 10;
 
-    let fns: Vec<(&'static str, fn(&str) -> i64)> = vec![("part1", part1), ("part2", part2)];
+    let mut fns: Vec<(&'static str, fn(&str) -> i64)> = vec![("part1", part1), ("part2", part2)];
+    if let Err(e) = assume::check(ASSUMPTIONS, 2, &input) {
+        println!("  part2: {e}");
+        fns.retain(|(name, _)| *name != "part2");
+    } else {
+        let circuit = Circuit::parse(&input);
+        for s in rx_subcircuits(&input).unwrap() {
+            println!(
+                "  {}: {} modules, fires every {} presses",
+                circuit.label(s.output),
+                s.modules.len(),
+                s.period
+            );
+        }
+    }
 
     for (name, f) in &fns {
         println!("  {name}: {}", f(&input));
@@ -245,14 +678,162 @@ fn test_part1_example_2() {
     assert_eq!(part1(input), 11687500);
 }
 
+#[test]
+fn test_dot() {
+    let input = r#"broadcaster -> a
+%a -> inv, con
+&inv -> b
+%b -> con
+&con -> rx"#;
+    let dot = dot(input);
+    assert!(dot.contains(r#""broadcaster" [shape="doublecircle"];"#));
+    assert!(dot.contains(r#""inv" [shape="diamond"];"#));
+    assert!(dot.contains(r#""a" -> "con" [color="red", penwidth="3"];"#));
+    assert!(dot.contains(r#""con" -> "rx";"#));
+}
+
+#[test]
+fn test_vcd() {
+    let input = r#"broadcaster -> a
+%a -> inv, con
+&inv -> b
+%b -> con
+&con -> output"#;
+    let vcd = vcd(input, 2);
+    assert!(vcd.contains("$scope module con $end\n$var wire 2 ) memory $end\n"));
+    assert!(vcd.contains("$scope module output $end\n$var wire 1 + in $end\n"));
+    // the first press takes steps 0 to 5, and con remembers two highs just before it sends low
+    let (first, second) = vcd.split_once("#7\n").unwrap();
+    assert!(first.contains("#1\nb00000000000000000000000000000001 !\n"));
+    assert!(first.contains("#5\n1(\nb11 )\n#6\n0*\n0+\n"));
+    assert!(second.starts_with("b00000000000000000000000000000010 !\n"));
+}
+
+#[test]
+fn test_assumptions() {
+    let input = utils::input::read(INPUT_FILE);
+    assert_eq!(assume::check(ASSUMPTIONS, 2, &input), Ok(()));
+    let mut rng = rand::SeedableRng::seed_from_u64(20);
+    assert_eq!(
+        assume::check(ASSUMPTIONS, 2, &generate(3, &mut rng)),
+        Ok(())
+    );
+
+    let reason = |input: &str| assume::check(ASSUMPTIONS, 2, input).unwrap_err().reason;
+    let input = r#"broadcaster -> a
+%a -> inv, con
+&inv -> b
+%b -> con
+&con -> rx"#;
+    assert_eq!(reason(input), "the sub-circuits behind %a and %b share %a");
+    assert_eq!(
+        reason("broadcaster -> a\n%a -> rx, b\n&b -> rx"),
+        "rx is fed by %a, &b"
+    );
+    assert_eq!(reason("broadcaster -> rx"), "rx is fed by broadcaster");
+    assert_eq!(reason("broadcaster -> a\n%a -> b"), "there's no rx");
+    assert_eq!(
+        reason("broadcaster -> a\n%a -> con\n&con -> rx, a"),
+        "&con feeds back into %a"
+    );
+    assert_eq!(
+        reason("broadcaster -> a\n%a -> con\n&con -> rx"),
+        "%a is still sending &con high at the end of press 1"
+    );
+    assert_eq!(
+        reason("broadcaster -> a\n%a -> broadcaster, inv\n&inv -> con\n&con -> rx"),
+        "the broadcaster is fed by %a"
+    );
+}
+
+#[test]
+fn test_part2_small() {
+    let mut rng = rand::SeedableRng::seed_from_u64(40);
+    for periods in [&[3, 5, 7][..], &[5, 7], &[3], &[7, 7], &[1, 3]] {
+        let input = counters(periods, 3, &mut rng);
+        let subcircuits = rx_subcircuits(&input).unwrap();
+        assert_eq!(
+            subcircuits.iter().map(|s| s.period).sorted().collect_vec(),
+            periods.to_vec(),
+            "{input}"
+        );
+        utils::assert_variants_agree(&input, &[("part2", part2), ("part2_brute", part2_brute)]);
+    }
+}
+
+#[test]
+fn test_press() {
+    let input = r#"broadcaster -> a
+%a -> inv, con
+&inv -> b
+%b -> con
+&con -> output"#;
+    let mut circuit = Circuit::parse(input);
+    assert_eq!(
+        circuit.names,
+        ["broadcaster", "a", "inv", "b", "con", "output", "button"]
+    );
+    let log = circuit
+        .press_logged()
+        .iter()
+        .map(|p| {
+            let high = if p.high { "high" } else { "low" };
+            let (from, to) = (circuit.names[p.from], circuit.names[p.to]);
+            format!("{} {from} -{high}-> {to}", p.step)
+        })
+        .collect_vec();
+    assert_eq!(
+        log,
+        [
+            "0 button -low-> broadcaster",
+            "1 broadcaster -low-> a",
+            "2 a -high-> inv",
+            "2 a -high-> con",
+            "3 inv -low-> b",
+            "3 con -high-> output",
+            "4 b -high-> con",
+            "5 con -low-> output",
+        ]
+    );
+    assert_eq!(circuit.state(&[1, 2, 3, 4]), [true, true, true, true, true]);
+    for _ in 0..3 {
+        circuit.press(|_| {});
+    }
+    assert_eq!(circuit.presses, 4);
+    assert_eq!(
+        circuit.state(&[1, 2, 3, 4]),
+        [false, false, false, false, false]
+    );
+    assert_eq!(circuit.upstream(4), [1, 2, 3, 4]);
+}
+
 #[test]
 fn test_part1_facit() {
-    let input = std::fs::read_to_string(INPUT_FILE).unwrap();
+    let input = utils::input::read(INPUT_FILE);
     assert_eq!(part1(&input), 808146535);
 }
 
 #[test]
 fn test_part2_facit() {
-    let input = std::fs::read_to_string(INPUT_FILE).unwrap();
+    let input = utils::input::read(INPUT_FILE);
     assert_eq!(part2(&input), 224602953547789);
 }
+
+#[test]
+fn test_generate() {
+    let mut rng = rand::SeedableRng::seed_from_u64(7);
+    let mut answer = part2(&generate(4, &mut rng)) as usize;
+    // should be the product of four distinct primes, one per counter
+    let mut factors = vec![];
+    for d in 2.. {
+        if answer == 1 {
+            break;
+        }
+        while answer % d == 0 {
+            factors.push(d);
+            answer /= d;
+        }
+    }
+    assert_eq!(factors.len(), 4);
+    assert!(factors.iter().all(|p| (2048..4096).contains(p)));
+}
@@ -1,8 +1,13 @@
-use std::collections::{HashMap, HashSet, VecDeque};
-
 use itertools::Itertools;
+use rand::rngs::StdRng;
+use rustc_hash::FxHashMap;
 
-use utils::{Grid, Pos, PosUtils, Vec2dUtils};
+use utils::{
+    assume::{self, Assumption},
+    gen,
+    search::Search,
+    Grid, Pos, PosUtils, Vec2dUtils,
+};
 
 static INPUT_FILE: &str = "input/day21";
 #[allow(dead_code)]
@@ -25,38 +30,142 @@ pub fn part1_real(input: &str) -> i64 {
     part1(input, 64)
 }
 pub fn part1(input: &str, num_steps: i64) -> i64 {
-    let mut grid: Grid<char> = input.lines().map(|l| l.chars().collect()).collect();
-    let start: Pos = grid
-        .iter()
-        .enumerate()
-        .flat_map(|(row, line)| Some((row, line.iter().find_position(|c| **c == 'S')?.0)))
-        .next()
-        .unwrap();
-    grid[start.0][start.1] = '.';
+    let num_steps = num_steps as usize;
+    reachable_counts(input, num_steps, false)[num_steps]
+}
 
-    let mut ends = HashSet::<Pos>::new();
-    let mut seen = HashMap::<Pos, i64>::new();
-    let mut q = VecDeque::<(Pos, i64)>::new();
-    q.push_back((start, 0));
-    while let Some((pos, steps)) = q.pop_front() {
-        if !seen.contains_key(&pos) && steps <= num_steps {
-            seen.insert(pos, steps);
-            if steps == num_steps {
-                ends.insert(pos);
-            } else {
-                for neigh in pos.neighbors_in(&grid) {
-                    if grid.at(neigh) == '.' {
-                        q.push_back((neigh, steps + 1));
-                    }
-                }
-            }
+/// How many plots can be reached in exactly `s` steps, for every `s` up to `max_steps`. A plot
+/// `d` steps away can also be reached in `d + 2`, `d + 4`, ... steps by stepping back and forth,
+/// so that's the plots at most `s` steps away with the same parity as `s`. With `tiled` the
+/// garden repeats forever in every direction.
+fn reachable_counts(input: &str, max_steps: usize, tiled: bool) -> Vec<i64> {
+    let mut at_distance = vec![0; max_steps + 1];
+    for row in distances_by_tile_row(input, max_steps, tiled).values() {
+        for (d, n) in row.iter().enumerate() {
+            at_distance[d] += n;
         }
     }
+    same_parity_sums(at_distance)
+}
+
+/// How many plots are exactly `d` steps from the start, for every `d` up to `max_steps`, split up
+/// by which row of copies of the garden they're in. Row 0 is the one the start is in.
+fn distances_by_tile_row(input: &str, max_steps: usize, tiled: bool) -> FxHashMap<i64, Vec<i64>> {
+    let grid: Grid<char> = input.lines().map(|l| l.chars().collect()).collect();
+    let (h, w) = (grid.len() as i64, grid[0].len() as i64);
+    let start = (0..h)
+        .cartesian_product(0..w)
+        .find(|(row, col)| grid[*row as usize][*col as usize] == 'S')
+        .unwrap();
+
+    let grid = &grid;
+    let found = Search::from([start]).max_cost(max_steps as u64).bfs(
+        |&(row, col)| {
+            [
+                (row - 1, col),
+                (row + 1, col),
+                (row, col - 1),
+                (row, col + 1),
+            ]
+            .into_iter()
+            .filter(move |(r, c)| tiled || (0..h).contains(r) && (0..w).contains(c))
+            .filter(move |(r, c)| grid[r.rem_euclid(h) as usize][c.rem_euclid(w) as usize] != '#')
+        },
+        |_| false,
+    );
+    let mut by_row: FxHashMap<i64, Vec<i64>> = FxHashMap::default();
+    for (&(row, _), d) in found.costs() {
+        by_row
+            .entry(row.div_euclid(h))
+            .or_insert_with(|| vec![0; max_steps + 1])[d as usize] += 1;
+    }
+    by_row
+}
+
+/// Turns counts of plots exactly `d` steps away into counts of plots reachable in exactly `s`.
+fn same_parity_sums(mut counts: Vec<i64>) -> Vec<i64> {
+    for s in 2..counts.len() {
+        counts[s] += counts[s - 2];
+    }
+    counts
+}
+
+/// How many plots can be reached in exactly `steps` steps in the infinitely tiled garden.
+///
+/// Far enough from the start, each row of copies of the garden is filled the same way as the row
+/// before it, just `h` steps later. And once a row has been reached for a while, every `w` steps
+/// adds one more filled copy at each end of it, so its count grows linearly. Both of those show
+/// up within a few garden widths of the start, so a BFS that far out is enough to extrapolate the
+/// count of every row, and summing the rows gives the count for any number of steps.
+pub fn part2_steps(input: &str, steps: usize) -> i64 {
+    // how far to search, in garden heights plus widths, and how many equal differences in a row
+    // count as a row growing linearly
+    const SPAN: usize = 6;
+    const SETTLED: usize = 3;
+    let h = input.lines().count();
+    let w = input.lines().next().unwrap().len();
+    let max_steps = steps.min(SPAN * (h + w));
+    let rows = distances_by_tile_row(input, max_steps, true)
+        .into_iter()
+        .map(|(row, counts)| (row, same_parity_sums(counts)))
+        .collect::<FxHashMap<_, _>>();
+    if steps == max_steps {
+        return rows.values().map(|counts| counts[steps]).sum();
+    }
+
+    let zeros = vec![0; max_steps + 1];
+    let row = |i: i64| rows.get(&i).unwrap_or(&zeros);
+    // the count in row `i` after `s` steps, extrapolating past max_steps
+    let count = |i: i64, s: usize| -> i64 {
+        let counts = row(i);
+        if s <= max_steps {
+            return counts[s];
+        }
+        let last = s - (s - max_steps).div_ceil(w) * w;
+        let samples = (0..=SETTLED)
+            .map(|k| counts[last - (SETTLED - k) * w])
+            .collect_vec();
+        let diffs = samples.windows(2).map(|v| v[1] - v[0]).collect_vec();
+        assert!(
+            diffs.iter().all_equal(),
+            "row {i} of the garden doesn't settle into linear growth"
+        );
+        samples[SETTLED] + ((s - last) / w) as i64 * diffs[0]
+    };
 
-    seen.into_iter().filter(|v| v.1 % 2 == 0).count() as i64
+    // the first row out from the start that every row further out repeats, on either side
+    let last_row = rows.keys().map(|i| i.abs()).max().unwrap();
+    let repeats_from = |n: i64, sign: i64| {
+        (n + 1..=last_row + 1).all(|i| {
+            let shift = (i - n) as usize * h;
+            (0..=max_steps)
+                .all(|s| row(sign * i)[s] == s.checked_sub(shift).map_or(0, |s| row(sign * n)[s]))
+        })
+    };
+    let n = (1..=last_row)
+        .find(|n| repeats_from(*n, 1) && repeats_from(*n, -1))
+        .expect("the rows of the garden don't settle into repeating each other");
+
+    let near = (1 - n..n).map(|i| count(i, steps)).sum::<i64>();
+    let far = [n, -n]
+        .into_iter()
+        .flat_map(|i| (0..=steps / h).map(move |k| (i, steps - k * h)))
+        .map(|(i, s)| count(i, s))
+        .sum::<i64>();
+    near + far
+}
+
+/// Brute force BFS over the infinitely tiled garden, for checking `part2_steps` against.
+pub fn part2_brute(input: &str, steps: usize) -> i64 {
+    reachable_counts(input, steps, true)[steps]
 }
 
 pub fn part2(input: &str) -> i64 {
+    part2_steps(input, PART2_STEPS)
+}
+
+/// Only works for inputs that satisfy [`GEOMETRIC_ASSUMPTIONS`], like the real ones do.
+pub fn part2_geometric(input: &str) -> i64 {
     let mut grid: Grid<char> = input.lines().map(|l| l.chars().collect()).collect();
     let start: Pos = grid
         .iter()
@@ -66,40 +175,150 @@ pub fn part2(input: &str) -> i64 {
         .unwrap();
     grid[start.0][start.1] = '.';
     let h = grid.len();
-    let mut ends = HashSet::<Pos>::new();
-    let mut seen = HashMap::<Pos, i64>::new();
-    let mut q = VecDeque::<(Pos, i64)>::new();
-    q.push_back((start, 0));
-    while let Some((pos, steps)) = q.pop_front() {
-        if !seen.contains_key(&pos) {
-            seen.insert(pos, steps);
-            ends.insert(pos);
-            for neigh in pos.neighbors_in(&grid) {
-                if grid.at(neigh) == '.' {
-                    q.push_back((neigh, steps + 1));
-                }
-            }
-        }
-    }
+    let found = Search::from([start]).bfs(
+        |pos| {
+            pos.neighbors_in(&grid)
+                .into_iter()
+                .filter(|neigh| grid.at(*neigh) == '.')
+        },
+        |_| false,
+    );
+    let seen = found.costs().map(|(_, steps)| steps as i64).collect_vec();
 
     // based off of this angel's breakdown: https://github.com/villuna/aoc23/wiki/A-Geometric-solution-to-advent-of-code-2023,-day-21
-    let even_corners = seen.values().filter(|v| **v % 2 == 0 && **v > 65).count();
-    let odd_corners = seen.values().filter(|v| **v % 2 == 1 && **v > 65).count();
-    let n = ((26501365 - (h / 2)) / h) as usize;
-    assert_eq!(n, 202300);
-    let even_full = seen.values().filter(|v| **v % 2 == 0).count();
-    let odd_full = seen.values().filter(|v| **v % 2 == 1).count();
+    let even_corners = seen
+        .iter()
+        .filter(|v| **v % 2 == 0 && **v > h as i64 / 2)
+        .count();
+    let odd_corners = seen
+        .iter()
+        .filter(|v| **v % 2 == 1 && **v > h as i64 / 2)
+        .count();
+    let n = (PART2_STEPS - (h / 2)) / h;
+    let even_full = seen.iter().filter(|v| **v % 2 == 0).count();
+    let odd_full = seen.iter().filter(|v| **v % 2 == 1).count();
     let p2 = ((n + 1) * (n + 1)) * odd_full + (n * n) * even_full - (n + 1) * odd_corners
         + n * even_corners;
     p2 as i64
 }
 
+const PART2_STEPS: usize = 26501365;
+
+/// What `part2_geometric` relies on. `part2` works for any garden.
+pub static GEOMETRIC_ASSUMPTIONS: &[Assumption] = &[
+    Assumption {
+        part: 2,
+        name: "square_grid",
+        description: "the garden is square with an odd side, and S is right in the middle",
+        check: check_square,
+    },
+    Assumption {
+        part: 2,
+        name: "clear_middle",
+        description: "the middle row and column and the border are all garden plots, so every \
+            copy of the garden is first reached from the middle of an edge or a corner",
+        check: check_clear_middle,
+    },
+    Assumption {
+        part: 2,
+        name: "steps_reach_edge",
+        description: "26501365 steps is half the side plus a whole number of sides, so the \
+            reachable area ends exactly on the edge of a copy of the garden",
+        check: |input| {
+            let h = input.lines().count();
+            match (PART2_STEPS - h / 2) % h {
+                0 => Ok(()),
+                rem => Err(format!("26501365 is {} + {h}n + {rem}", h / 2)),
+            }
+        },
+    },
+];
+
+fn check_square(input: &str) -> Result<(), String> {
+    let grid: Grid<char> = input.lines().map(|l| l.chars().collect()).collect();
+    let h = grid.len();
+    if let Some(row) = grid.iter().position(|line| line.len() != h) {
+        return Err(format!(
+            "{h} rows, but row {row} is {} wide",
+            grid[row].len()
+        ));
+    }
+    if h % 2 == 0 {
+        return Err(format!("the side is {h}, which is even"));
+    }
+    let starts = (0..h)
+        .cartesian_product(0..h)
+        .filter(|pos| grid.at(*pos) == 'S')
+        .collect_vec();
+    match starts[..] {
+        [(row, col)] if (row, col) == (h / 2, h / 2) => Ok(()),
+        [(row, col)] => Err(format!("S is at {row},{col}, not {0},{0}", h / 2)),
+        _ => Err("there isn't exactly one S".to_string()),
+    }
+}
+
+fn check_clear_middle(input: &str) -> Result<(), String> {
+    let grid: Grid<char> = input.lines().map(|l| l.chars().collect()).collect();
+    let (h, mid) = (grid.len(), grid.len() / 2);
+    let rock = (0..h)
+        .cartesian_product(0..h)
+        .filter(|pos| grid.at(*pos) == '#')
+        .find(|(row, col)| [row, col].iter().any(|v| [0, mid, h - 1].contains(v)));
+    match rock {
+        Some((row, col)) => Err(format!("there's a rock at {row},{col}")),
+        None => Ok(()),
+    }
+}
+
+/// A random `size` by `size` garden (rounded up to an odd size) shaped like the real inputs: the
+/// start in the middle, a clear border, clear middle row and column, and a clear diamond between
+/// them. Plots that can't be reached are filled in with rocks.
+pub fn generate(size: usize, rng: &mut StdRng) -> String {
+    let size = size.max(3) | 1;
+    let mid = size / 2;
+    let mut grid = gen::grid(rng, size, size, &[('.', 7), ('#', 1)]);
+    for (row, line) in grid.iter_mut().enumerate() {
+        for (col, c) in line.iter_mut().enumerate() {
+            let on_diamond = row.abs_diff(mid) + col.abs_diff(mid) == mid;
+            let on_border = row == 0 || col == 0 || row == size - 1 || col == size - 1;
+            if on_diamond || on_border || row == mid || col == mid {
+                *c = '.';
+            }
+        }
+    }
+    let found = Search::from([(mid, mid)]).bfs(
+        |pos| {
+            pos.neighbors_in(&grid)
+                .into_iter()
+                .filter(|neigh| grid.at(*neigh) == '.')
+        },
+        |_| false,
+    );
+    for (row, line) in grid.iter_mut().enumerate() {
+        for (col, c) in line.iter_mut().enumerate() {
+            if found.cost_of(&(row, col)).is_none() {
+                *c = '#';
+            }
+        }
+    }
+    grid[mid][mid] = 'S';
+    gen::grid_to_string(&grid)
+}
+
 pub fn main(bench: bool) {
-    let input = std::fs::read_to_string(INPUT_FILE).unwrap();
+    let input = utils::input::read(INPUT_FILE);
 
     let iters = 10;
 
-    let fns: Vec<(&'static str, fn(&str) -> i64)> = vec![("part1", part1_real), ("part2", part2)];
+    let mut fns: Vec<(&'static str, fn(&str) -> i64)> = vec![
+        ("part1", part1_real),
+        ("part2", part2),
+        ("part2_geometric", part2_geometric),
+    ];
+    if let Err(e) = assume::check(GEOMETRIC_ASSUMPTIONS, 2, &input) {
+        println!("  part2_geometric: {e}");
+        fns.retain(|(name, _)| *name != "part2_geometric");
+    }
 
     for (name, f) in &fns {
         println!("  {name}: {}", f(&input));
@@ -128,14 +347,75 @@ fn test_part1_example() {
     assert_eq!(part1_example(EXAMPLE_INPUT), 16);
 }
 
+#[test]
+fn test_assumptions() {
+    let input = utils::input::read(INPUT_FILE);
+    assert_eq!(assume::check(GEOMETRIC_ASSUMPTIONS, 2, &input), Ok(()));
+    let mut rng = rand::SeedableRng::seed_from_u64(21);
+    assert_eq!(
+        assume::check(GEOMETRIC_ASSUMPTIONS, 2, &generate(131, &mut rng)),
+        Ok(())
+    );
+
+    let violation = assume::check(GEOMETRIC_ASSUMPTIONS, 2, EXAMPLE_INPUT).unwrap_err();
+    assert_eq!(violation.name, "clear_middle");
+    assert_eq!(violation.reason, "there's a rock at 1,5");
+    let violation = assume::check(GEOMETRIC_ASSUMPTIONS, 2, &generate(11, &mut rng)).unwrap_err();
+    assert_eq!(violation.reason, "26501365 is 5 + 11n + 6");
+    let violation = assume::check(GEOMETRIC_ASSUMPTIONS, 2, "...\n.S.\n..").unwrap_err();
+    assert_eq!(violation.reason, "3 rows, but row 2 is 2 wide");
+}
+
+#[test]
+fn test_part2_example() {
+    for (steps, expected) in [(6, 16), (10, 50), (50, 1594), (100, 6536), (500, 167004)] {
+        assert_eq!(part2_brute(EXAMPLE_INPUT, steps), expected);
+        assert_eq!(part2_steps(EXAMPLE_INPUT, steps), expected);
+    }
+    assert_eq!(part2_steps(EXAMPLE_INPUT, 1000), 668697);
+    assert_eq!(part2_steps(EXAMPLE_INPUT, 5000), 16733044);
+}
+
+#[test]
+fn test_part2_matches_brute_force() {
+    let input = utils::input::read(INPUT_FILE);
+    let mut rng = rand::SeedableRng::seed_from_u64(21);
+    let generated = generate(21, &mut rng);
+    for input in [EXAMPLE_INPUT, &generated, &input] {
+        for steps in [6, 10, 50, 100, 500, 1000] {
+            assert_eq!(
+                part2_steps(input, steps),
+                part2_brute(input, steps),
+                "{steps} steps"
+            );
+        }
+    }
+}
+
+#[test]
+fn test_part2_extrapolates_past_the_search() {
+    // far past the 6 * (h + w) steps part2_steps searches, and with a garden that isn't square
+    let narrow = EXAMPLE_INPUT.lines().map(|l| &l[..9]).join("\n");
+    for input in [EXAMPLE_INPUT, &narrow] {
+        for steps in [401, 1000] {
+            assert_eq!(
+                part2_steps(input, steps),
+                part2_brute(input, steps),
+                "{steps} steps"
+            );
+        }
+    }
+}
+
 #[test]
 fn test_part1_facit() {
-    let input = std::fs::read_to_string(INPUT_FILE).unwrap();
+    let input = utils::input::read(INPUT_FILE);
     assert_eq!(part1_real(&input), 3697);
 }
 
 #[test]
 fn test_part2_facit() {
-    let input = std::fs::read_to_string(INPUT_FILE).unwrap();
+    let input = utils::input::read(INPUT_FILE);
     assert_eq!(part2(&input), 608152828731262);
+    assert_eq!(part2_geometric(&input), 608152828731262);
 }
@@ -3,7 +3,9 @@ use std::{
     ops::RangeInclusive,
 };
 
-use utils::range_intersect;
+use itertools::Itertools;
+use rand::{rngs::StdRng, Rng};
+use utils::{event, range_intersect, trace::Level};
 
 static INPUT_FILE: &str = "input/day22";
 #[allow(dead_code)]
@@ -45,7 +47,161 @@ impl Brick {
     }
 }
 
+fn parse(input: &str) -> Vec<Brick> {
+    input
+        .lines()
+        .map(|l| {
+            let (start, end) = l.split_once('~').unwrap();
+            let start = Point::parse(start);
+            let end = Point::parse(end);
+            Brick {
+                x: start.x.min(end.x)..=start.x.max(end.x),
+                y: start.y.min(end.y)..=start.y.max(end.y),
+                z: start.z.min(end.z)..=start.z.max(end.z),
+            }
+        })
+        .collect()
+}
+
+/// Who rests on whom once everything's fallen. Bricks are numbered from the bottom up, so a
+/// brick's supporters always come before it.
+struct Settled {
+    supporters: Vec<Vec<usize>>,
+}
+
+/// Drops the bricks lowest first, so each one lands on whatever's highest under it. A heightmap
+/// of the top brick in every column says what that is without looking at the other bricks.
+fn settle(bricks: &mut [Brick]) -> Settled {
+    bricks.sort_unstable_by_key(|b| *b.z.start());
+    let w = bricks.iter().map(|b| *b.x.end()).max().unwrap_or(0) as usize + 1;
+    let d = bricks.iter().map(|b| *b.y.end()).max().unwrap_or(0) as usize + 1;
+    // the height of the top of each column, and the brick there
+    let mut top = vec![vec![(0, None); d]; w];
+    let mut supporters = vec![];
+    for (ix, brick) in bricks.iter_mut().enumerate() {
+        let columns = || {
+            brick
+                .x
+                .clone()
+                .cartesian_product(brick.y.clone())
+                .map(|(x, y)| (x as usize, y as usize))
+        };
+        let rest = columns().map(|(x, y)| top[x][y].0).max().unwrap();
+        let under = columns()
+            .filter_map(|(x, y)| top[x][y].1.filter(|_| top[x][y].0 == rest))
+            .sorted()
+            .dedup()
+            .collect_vec();
+        let fall = brick.z.start() - (rest + 1);
+        brick.z = (brick.z.start() - fall)..=(brick.z.end() - fall);
+        if fall > 0 {
+            event!(
+                Level::Trace,
+                "brick dropped",
+                brick = ix,
+                z = *brick.z.start()
+            );
+        }
+        for (x, y) in columns() {
+            top[x][y] = (*brick.z.end(), Some(ix));
+        }
+        supporters.push(under);
+    }
+    Settled { supporters }
+}
+
+impl Settled {
+    /// The dominator tree of the support graph, rooted at the ground, as each brick's parent
+    /// (`None` for the ground) and depth (the ground's is 0). A brick falls when any brick above
+    /// it in the tree is taken out, and only then: taking out `d` drops `b` exactly when every
+    /// way down from `b` to the ground goes through `d`. So a brick's parent is the lowest
+    /// common ancestor of its supporters, which are all placed by the time it is.
+    fn dominators(&self) -> (Vec<Option<usize>>, Vec<usize>) {
+        let n = self.supporters.len();
+        // the ground is node n, and up[k][b] is b's 2^k-th ancestor, sticking at the ground
+        let levels = (usize::BITS - n.leading_zeros()) as usize + 1;
+        let mut up = vec![vec![n; n + 1]; levels];
+        let mut depth = vec![0; n + 1];
+        let lca = |up: &[Vec<usize>], depth: &[usize], mut a: usize, mut b: usize| {
+            if depth[a] < depth[b] {
+                (a, b) = (b, a);
+            }
+            for k in (0..levels).rev() {
+                if depth[a] - depth[b] >= 1 << k {
+                    a = up[k][a];
+                }
+            }
+            if a == b {
+                return a;
+            }
+            for k in (0..levels).rev() {
+                if up[k][a] != up[k][b] {
+                    (a, b) = (up[k][a], up[k][b]);
+                }
+            }
+            up[0][a]
+        };
+        for (brick, supporters) in self.supporters.iter().enumerate() {
+            let parent = supporters
+                .iter()
+                .copied()
+                .reduce(|a, b| lca(&up, &depth, a, b))
+                .unwrap_or(n);
+            depth[brick] = depth[parent] + 1;
+            up[0][brick] = parent;
+            for k in 1..levels {
+                up[k][brick] = up[k - 1][up[k - 1][brick]];
+            }
+        }
+        let parents = up[0][..n]
+            .iter()
+            .map(|p| Some(*p).filter(|p| *p != n))
+            .collect();
+        depth.truncate(n);
+        (parents, depth)
+    }
+}
+
+/// How many other bricks fall if each brick is taken out, as the size of its subtree in the
+/// dominator tree.
+fn falling(settled: &Settled) -> Vec<usize> {
+    let (parents, _) = settled.dominators();
+    let mut below = vec![0; parents.len()];
+    // children always come after their parents
+    for brick in (0..parents.len()).rev() {
+        if let Some(parent) = parents[brick] {
+            below[parent] += below[brick] + 1;
+        }
+    }
+    below
+}
+
 pub fn part1(input: &str) -> i64 {
+    let mut bricks = parse(input);
+    let settled = settle(&mut bricks);
+    let mut safe = vec![true; bricks.len()];
+    for supporters in &settled.supporters {
+        if let [s] = supporters[..] {
+            safe[s] = false;
+        }
+    }
+    safe.iter().filter(|safe| **safe).count() as i64
+}
+
+/// Every brick falls when any of the bricks strictly above it in the dominator tree is taken
+/// out, so adding that up for every brick is the same as adding up all the depths.
+pub fn part2(input: &str) -> i64 {
+    let mut bricks = parse(input);
+    let (_, depth) = settle(&mut bricks).dominators();
+    depth.iter().map(|d| d - 1).sum::<usize>() as i64
+}
+
+pub fn part2_subtrees(input: &str) -> i64 {
+    let mut bricks = parse(input);
+    falling(&settle(&mut bricks)).iter().sum::<usize>() as i64
+}
+
+pub fn part1_pairwise(input: &str) -> i64 {
     let mut bricks: Vec<Brick> = input
         .lines()
         .map(|l| {
@@ -75,6 +231,12 @@ pub fn part1(input: &str) -> i64 {
         for drop_ix in drop {
             let d = &mut bricks[drop_ix];
             d.z = (d.z.start() - 1)..=(d.z.end() - 1);
+            event!(
+                Level::Trace,
+                "brick dropped",
+                brick = drop_ix,
+                z = *d.z.start()
+            );
         }
     }
 
@@ -97,7 +259,7 @@ pub fn part1(input: &str) -> i64 {
     sole_supporters.len() as i64
 }
 
-pub fn part2(input: &str) -> i64 {
+pub fn part2_pairwise(input: &str) -> i64 {
     let mut bricks: Vec<Brick> = input
         .lines()
         .map(|l| {
@@ -125,6 +287,12 @@ pub fn part2(input: &str) -> i64 {
         for drop_ix in drop {
             let d = &mut bricks[drop_ix];
             d.z = (d.z.start() - 1)..=(d.z.end() - 1);
+            event!(
+                Level::Trace,
+                "brick dropped",
+                brick = drop_ix,
+                z = *d.z.start()
+            );
         }
     }
 
@@ -164,12 +332,54 @@ pub fn part2(input: &str) -> i64 {
     result as i64
 }
 
+/// `size` random bricks floating over a 10 by 10 area without overlapping.
+pub fn generate(size: usize, rng: &mut StdRng) -> String {
+    let max_z = (size / 4).max(10);
+    let mut taken = HashSet::<(usize, usize, usize)>::new();
+    let mut lines = vec![];
+    while lines.len() < size {
+        let start = (
+            rng.gen_range(0..10),
+            rng.gen_range(0..10),
+            rng.gen_range(1..=max_z),
+        );
+        let len = rng.gen_range(0..4);
+        let end = match rng.gen_range(0..3) {
+            0 => (start.0 + len, start.1, start.2),
+            1 => (start.0, start.1 + len, start.2),
+            _ => (start.0, start.1, start.2 + len),
+        };
+        if end.0 >= 10 || end.1 >= 10 {
+            continue;
+        }
+        let cubes = (start.0..=end.0)
+            .flat_map(|x| {
+                (start.1..=end.1).flat_map(move |y| (start.2..=end.2).map(move |z| (x, y, z)))
+            })
+            .collect_vec();
+        if cubes.iter().any(|c| taken.contains(c)) {
+            continue;
+        }
+        taken.extend(cubes);
+        lines.push(format!(
+            "{},{},{}~{},{},{}",
+            start.0, start.1, start.2, end.0, end.1, end.2
+        ));
+    }
+    lines.join("\n")
+}
+
 pub fn main(bench: bool) {
-    let input = std::fs::read_to_string(INPUT_FILE).unwrap();
+    let input = utils::input::read(INPUT_FILE);
 
     let iters = 1;
 
-    let fns: Vec<(&'static str, fn(&str) -> i64)> = vec![("part1", part1), ("part2", part2)];
+    // the pairwise versions take a second or so each, so they're only checked in the tests
+    let fns: Vec<(&'static str, fn(&str) -> i64)> = vec![
+        ("part1", part1),
+        ("part2", part2),
+        ("part2_subtrees", part2_subtrees),
+    ];
 
     for (name, f) in &fns {
         println!("  {name}: {}", f(&input));
@@ -203,15 +413,54 @@ fn test_part2_example() {
     assert_eq!(part2(EXAMPLE_INPUT), 7);
 }
 
+#[cfg(test)]
+fn arb_snapshot() -> impl proptest::strategy::Strategy<Value = String> {
+    use proptest::prelude::*;
+    (any::<u64>(), 1usize..200)
+        .prop_map(|(seed, size)| generate(size, &mut rand::SeedableRng::seed_from_u64(seed)))
+}
+
+#[cfg(test)]
+proptest::proptest! {
+    #[test]
+    fn test_variants_agree(input in arb_snapshot()) {
+        utils::assert_variants_agree(
+            &input,
+            &[("part1", part1), ("part1_pairwise", part1_pairwise)],
+        );
+        utils::assert_variants_agree(
+            &input,
+            &[
+                ("part2", part2),
+                ("part2_subtrees", part2_subtrees),
+                ("part2_pairwise", part2_pairwise),
+            ],
+        );
+    }
+}
+
+#[test]
+fn test_falling() {
+    let mut bricks = parse(EXAMPLE_INPUT);
+    let settled = settle(&mut bricks);
+    // A holds up everything, F holds up G, and nothing else matters on its own
+    assert_eq!(falling(&settled), vec![6, 0, 0, 0, 0, 1, 0]);
+    let (parents, _) = settled.dominators();
+    assert_eq!(
+        parents,
+        vec![None, Some(0), Some(0), Some(0), Some(0), Some(0), Some(5)]
+    );
+}
+
 #[test]
 fn test_part1_facit() {
-    let input = std::fs::read_to_string(INPUT_FILE).unwrap();
+    let input = utils::input::read(INPUT_FILE);
     assert_eq!(part1(&input), 386);
 }
 
 #[test]
 fn test_part2_facit() {
-    let input = std::fs::read_to_string(INPUT_FILE).unwrap();
+    let input = utils::input::read(INPUT_FILE);
     assert_eq!(part2(&input), 39933);
 }
 
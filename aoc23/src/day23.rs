@@ -1,8 +1,18 @@
-use std::collections::{HashMap, HashSet, VecDeque};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::atomic::{AtomicUsize, Ordering},
+};
 
 use itertools::Itertools;
+use rand::{rngs::StdRng, Rng};
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
 
-use utils::{Grid, Pos, PosUtils, SGrid, Vec2dUtils};
+use utils::{
+    dot::{Dot, HIGHLIGHT},
+    gen, maze,
+    maze::JunctionGraph,
+    Dir, Grid, Pos, PosUtils, SGrid, Vec2dUtils,
+};
 
 static INPUT_FILE: &str = "input/day23";
 #[allow(dead_code)]
@@ -30,7 +40,7 @@ static EXAMPLE_INPUT: &str = r#"#.#####################
 #.....###...###...#...#
 #####################.#"#;
 
-pub fn part1(input: &str) -> i64 {
+pub fn part1_grid(input: &str) -> i64 {
     let mut grid: Grid<char> = input.lines().map(|l| l.chars().collect()).collect();
     let end = (
         grid.len() - 1,
@@ -211,100 +221,364 @@ pub fn part1(input: &str) -> i64 {
 //     result.unwrap() as i64
 // }
 
-pub fn part2(input: &str) -> i64 {
-    fn remove_slope(v: char) -> char {
-        if v == '>' || v == 'v' {
-            '.'
+/// Compresses the map into a graph between its junctions, with `one_way` saying what to make of
+/// the slopes, and finds the start and end tiles in it.
+fn junction_graph(input: &str, one_way: fn(char) -> Option<Dir>) -> (JunctionGraph, usize, usize) {
+    let grid: Grid<char> = input.lines().map(|l| l.chars().collect()).collect();
+    let graph = maze::compress(&grid, |c| c != '#', one_way);
+    let open = |row: usize| (row, grid[row].iter().position(|c| *c == '.').unwrap());
+    let start = graph.index(open(0)).unwrap();
+    let end = graph.index(open(grid.len() - 1)).unwrap();
+    (graph, start, end)
+}
+
+/// Length of the longest simple path from `start` to `end`, and the junctions along it. Clones
+/// the path so far for every branch, so it's slow, but it's simple enough to check the others
+/// against.
+fn longest_path(graph: &JunctionGraph, start: usize, end: usize) -> (usize, Vec<usize>) {
+    let mut result = 0;
+    let mut path = vec![];
+    let mut q = Vec::<(usize, usize, Vec<usize>)>::new();
+    q.push((start, 0, vec![]));
+    while let Some((pos, len, seen)) = q.pop() {
+        if pos == end {
+            if len > result {
+                result = len;
+                path = seen;
+                path.push(pos);
+            }
+            continue;
         } else {
-            v
+            for (target, edge_len) in &graph.edges[pos] {
+                if !seen.contains(target) {
+                    let mut s = seen.clone();
+                    s.push(pos);
+                    q.push((*target, len + edge_len, s));
+                }
+            }
         }
     }
 
-    let grid: Grid<char> = input
-        .lines()
-        .map(|l| l.chars().map(remove_slope).collect())
-        .collect();
-    let end = (
-        grid.len() - 1,
-        grid.last()
-            .unwrap()
-            .iter()
-            .find_position(|c| **c == '.')
-            .unwrap()
-            .0,
-    );
+    (result, path)
+}
+
+/// The junction graph with the junctions numbered `0..n`, so that a set of them fits in a `u64`.
+struct Junctions {
+    pos: Vec<Pos>,
+    edges: Vec<Vec<(usize, usize)>>,
+    /// The longest edge into each junction. A hike can't get any longer than the edges it's
+    /// walked plus these for every junction it hasn't been to yet.
+    longest_in: Vec<usize>,
+    start: usize,
+    end: usize,
+}
 
-    let mut intersections = HashSet::<Pos>::new();
-    for (x, row) in grid.iter().enumerate() {
-        for (y, _) in row.iter().enumerate().filter(|v| *v.1 == '.') {
-            let pos: Pos = (x, y);
-            let mut neigh = pos.neighbors_in(&grid);
-            neigh.retain(|n| grid.at(*n) == '.');
-            if neigh.len() > 2 {
-                intersections.insert(pos);
+/// A hike still being walked, for [`Junctions::search`].
+#[derive(Debug, Clone)]
+struct Hike {
+    at: usize,
+    seen: u64,
+    len: usize,
+    /// The sum of `longest_in` over the junctions not in `seen`.
+    remaining: usize,
+    path: Vec<usize>,
+}
+
+impl Junctions {
+    fn new(graph: &JunctionGraph, start: usize, end: usize) -> Junctions {
+        assert!(
+            graph.nodes.len() <= 64,
+            "there are {} junctions, which don't fit in a u64",
+            graph.nodes.len()
+        );
+        let mut edges = graph.edges.clone();
+        let mut longest_in = vec![0; graph.nodes.len()];
+        for targets in &mut edges {
+            for (to, len) in targets.iter() {
+                longest_in[*to] = longest_in[*to].max(*len);
             }
+            // trying long edges first finds long hikes sooner, which makes for a better bound
+            targets.sort_by_key(|(_, len)| std::cmp::Reverse(*len));
+        }
+        // going anywhere else from the only junction that leads to the end would cut it off for
+        // good
+        let into_end = (0..edges.len())
+            .filter(|from| edges[*from].iter().any(|(to, _)| *to == end))
+            .collect_vec();
+        if let [last] = into_end[..] {
+            edges[last].retain(|(to, _)| *to == end);
         }
+        Junctions {
+            pos: graph.nodes.clone(),
+            edges,
+            longest_in,
+            start,
+            end,
+        }
+    }
+
+    fn start(&self) -> Hike {
+        Hike {
+            at: self.start,
+            seen: 1 << self.start,
+            len: 0,
+            remaining: self.longest_in.iter().sum::<usize>() - self.longest_in[self.start],
+            path: vec![self.start],
+        }
+    }
+
+    /// Every way to take one more edge from `hike`.
+    fn steps<'a>(&'a self, hike: &'a Hike) -> impl Iterator<Item = Hike> + 'a {
+        self.edges[hike.at]
+            .iter()
+            .filter(|(to, _)| hike.seen & 1 << to == 0)
+            .map(|(to, len)| Hike {
+                at: *to,
+                seen: hike.seen | 1 << to,
+                len: hike.len + len,
+                remaining: hike.remaining - self.longest_in[*to],
+                path: hike.path.iter().copied().chain([*to]).collect(),
+            })
     }
-    intersections.insert((0, 1));
-    intersections.insert(end);
-
-    let mut edges: HashMap<Pos, Vec<(Pos, usize)>> = HashMap::new();
-    for intersection in intersections.iter() {
-        let mut q = VecDeque::new();
-        let mut seen = HashSet::new();
-        q.push_back((*intersection, 0usize));
-        while let Some((pos, len)) = q.pop_front() {
-            if !seen.insert(pos) {
+
+    /// Depth-first over every way to finish `hike`, keeping the longest in `best` and skipping
+    /// any that couldn't get longer than `bound`, which is the longest found by anyone so far.
+    fn search(&self, hike: &mut Hike, bound: &AtomicUsize, best: &mut Option<(usize, Vec<usize>)>) {
+        if hike.at == self.end {
+            if best.as_ref().is_none_or(|(len, _)| hike.len > *len) {
+                *best = Some((hike.len, hike.path.clone()));
+                bound.fetch_max(hike.len, Ordering::Relaxed);
+            }
+            return;
+        }
+        if hike.len + hike.remaining <= bound.load(Ordering::Relaxed) {
+            return;
+        }
+        let (at, len, remaining) = (hike.at, hike.len, hike.remaining);
+        for &(to, edge) in &self.edges[at] {
+            if hike.seen & 1 << to != 0 {
                 continue;
             }
-            for neigh in pos
-                .neighbors_in(&grid)
+            hike.at = to;
+            hike.seen |= 1 << to;
+            hike.len = len + edge;
+            hike.remaining = remaining - self.longest_in[to];
+            hike.path.push(to);
+            self.search(hike, bound, best);
+            hike.path.pop();
+            hike.seen ^= 1 << to;
+        }
+        (hike.at, hike.len, hike.remaining) = (at, len, remaining);
+    }
+
+    fn positions(&self, path: &[usize]) -> Vec<Pos> {
+        path.iter().map(|ix| self.pos[*ix]).collect()
+    }
+
+    /// The longest hike to the end, and the junctions along it.
+    fn longest_hike(&self) -> Option<(usize, Vec<Pos>)> {
+        let mut best = None;
+        self.search(&mut self.start(), &AtomicUsize::new(0), &mut best);
+        best.map(|(len, path)| (len, self.positions(&path)))
+    }
+
+    /// Like [`Junctions::longest_hike`], but splits the first few junctions' worth of branches
+    /// across threads. They share the longest hike found so far, so they can all prune with it.
+    fn longest_hike_par(&self) -> Option<(usize, Vec<Pos>)> {
+        let mut branches = vec![self.start()];
+        while branches.len() < 256 {
+            let next = branches
                 .iter()
-                .filter(|v| grid.at(**v) == '.')
-            {
-                if intersections.contains(neigh) && neigh != intersection {
-                    edges
-                        .entry(*intersection)
-                        .or_default()
-                        .push((*neigh, len + 1));
-                    continue;
-                } else {
-                    q.push_back((*neigh, len + 1));
-                }
+                .flat_map(|hike| {
+                    if hike.at == self.end {
+                        vec![hike.clone()]
+                    } else {
+                        self.steps(hike).collect()
+                    }
+                })
+                .collect_vec();
+            if next.len() <= branches.len() {
+                break;
             }
+            branches = next;
         }
+        let bound = AtomicUsize::new(0);
+        branches
+            .into_par_iter()
+            .filter_map(|mut hike| {
+                let mut best = None;
+                self.search(&mut hike, &bound, &mut best);
+                best
+            })
+            .max_by_key(|(len, _)| *len)
+            .map(|(len, path)| (len, self.positions(&path)))
     }
+}
 
-    let mut result = 0;
-    // TODO: would be nice to not have to clone the prior path all the time? can we push a seen
-    // value to the stack instead and maintain a hashset? maybe we should just recurse instead
-    let mut q = Vec::<(Pos, usize, Vec<Pos>)>::new();
-    q.push(((0, 1), 0, vec![]));
-    while let Some((pos, len, seen)) = q.pop() {
-        if pos == end {
-            result = result.max(len as i64);
-            continue;
+pub fn part1(input: &str) -> i64 {
+    let (graph, start, end) = junction_graph(input, maze::slope);
+    let junctions = Junctions::new(&graph, start, end);
+    junctions
+        .longest_hike()
+        .expect("there's no way to the end")
+        .0 as i64
+}
+
+pub fn part2(input: &str) -> i64 {
+    let (graph, start, end) = junction_graph(input, maze::two_way);
+    let junctions = Junctions::new(&graph, start, end);
+    junctions
+        .longest_hike()
+        .expect("there's no way to the end")
+        .0 as i64
+}
+
+pub fn part2_par(input: &str) -> i64 {
+    let (graph, start, end) = junction_graph(input, maze::two_way);
+    let junctions = Junctions::new(&graph, start, end);
+    junctions
+        .longest_hike_par()
+        .expect("there's no way to the end")
+        .0 as i64
+}
+
+pub fn part2_cloning(input: &str) -> i64 {
+    let (graph, start, end) = junction_graph(input, maze::two_way);
+    longest_path(&graph, start, end).0 as i64
+}
+
+/// The compressed junction graph as DOT, weighted by corridor length, with the longest hike
+/// highlighted.
+pub fn dot(input: &str) -> String {
+    let (graph, start, end) = junction_graph(input, maze::two_way);
+    let (_, path) = Junctions::new(&graph, start, end)
+        .longest_hike()
+        .unwrap_or_default();
+    let (start, end) = (graph.nodes[start], graph.nodes[end]);
+    let on_path = path
+        .iter()
+        .tuple_windows()
+        .flat_map(|(a, b)| [(*a, *b), (*b, *a)])
+        .collect::<HashSet<_>>();
+
+    let id = |(r, c): Pos| format!("{r},{c}");
+    let mut dot = Dot::graph("day23");
+    dot.node(&id(start), &[("shape", "doublecircle")])
+        .node(&id(end), &[("shape", "doublecircle")]);
+    for (from, targets) in graph.edges.iter().enumerate() {
+        let from = graph.nodes[from];
+        for (to, len) in targets {
+            let to = graph.nodes[*to];
+            if from > to {
+                continue;
+            }
+            let len = len.to_string();
+            let mut attrs = vec![("label", len.as_str())];
+            if on_path.contains(&(from, to)) {
+                attrs.extend_from_slice(HIGHLIGHT);
+            }
+            dot.edge(&id(from), &id(to), &attrs);
+        }
+    }
+    dot.to_string()
+}
+
+/// A random hiking map about `size` tiles across: a lattice of up to 6 by 6 junctions, each
+/// nudged a little off the grid, joined by sloped trails that jog halfway to make up for it.
+/// There's always a way from the start to the end that only walks down the slopes.
+pub fn generate(size: usize, rng: &mut StdRng) -> String {
+    let k = (size.saturating_sub(3) / 8 + 1).clamp(2, 6);
+    let spacing = (size.saturating_sub(3) / (k - 1)).max(8);
+    let n = (k - 1) * spacing + 3;
+    // keeps trails at least one tile apart from each other
+    let jitter = spacing / 4 - 1;
+    let mut junctions = HashMap::<(usize, usize), Pos>::new();
+    for i in 0..k {
+        for j in 0..k {
+            let mut nudge = || (1 + rng.gen_range(0..=2 * jitter)).saturating_sub(jitter);
+            let pos = (i * spacing + nudge(), j * spacing + nudge());
+            junctions.insert((i, j), (pos.0.clamp(1, n - 2), pos.1.clamp(1, n - 2)));
+        }
+    }
+    // the start and end tiles lead straight into the corner junctions
+    junctions.insert((0, 0), (1, 1));
+    junctions.insert((k - 1, k - 1), (n - 2, n - 2));
+
+    // trails as (junction, true if going right else down)
+    let mut trails = vec![];
+    let mut pos = (0, 0);
+    while pos != (k - 1, k - 1) {
+        let right = pos.0 == k - 1 || (pos.1 < k - 1 && rng.gen());
+        trails.push((pos, right));
+        pos = if right {
+            (pos.0, pos.1 + 1)
         } else {
-            let targets = edges.get(&pos).unwrap();
-            for (target, edge_len) in targets {
-                if !seen.contains(target) {
-                    let mut s = seen.clone();
-                    s.push(pos);
-                    q.push((*target, len + edge_len, s));
-                }
+            (pos.0 + 1, pos.1)
+        };
+    }
+    for row in 0..k {
+        for col in 0..k {
+            if col < k - 1 && rng.gen_range(0..10) < 7 {
+                trails.push(((row, col), true));
+            }
+            if row < k - 1 && rng.gen_range(0..10) < 7 {
+                trails.push(((row, col), false));
             }
         }
     }
 
-    result
+    let mut grid = vec![vec!['#'; n]; n];
+    grid[0][1] = '.';
+    grid[n - 1][n - 2] = '.';
+    for ((row, col), right) in trails {
+        let (from, to, slope) = if right {
+            (junctions[&(row, col)], junctions[&(row, col + 1)], '>')
+        } else {
+            (junctions[&(row, col)], junctions[&(row + 1, col)], 'v')
+        };
+        let mut path = vec![from];
+        let mut pos = from;
+        let mid = if right {
+            (from.1 + to.1) / 2
+        } else {
+            (from.0 + to.0) / 2
+        };
+        while pos != to {
+            let along = if right { pos.1 } else { pos.0 };
+            let across = if right { (pos.0, to.0) } else { (pos.1, to.1) };
+            pos = match (along == mid && across.0 != across.1, right) {
+                (false, true) => (pos.0, pos.1 + 1),
+                (false, false) => (pos.0 + 1, pos.1),
+                (true, true) if across.0 < across.1 => (pos.0 + 1, pos.1),
+                (true, true) => (pos.0 - 1, pos.1),
+                (true, false) if across.0 < across.1 => (pos.0, pos.1 + 1),
+                (true, false) => (pos.0, pos.1 - 1),
+            };
+            path.push(pos);
+        }
+        for (ix, (r, c)) in path.iter().enumerate() {
+            grid[*r][*c] = if ix == 1 || ix == path.len() - 2 {
+                slope
+            } else {
+                '.'
+            };
+        }
+    }
+    gen::grid_to_string(&grid)
 }
 
 pub fn main(bench: bool) {
-    let input = std::fs::read_to_string(INPUT_FILE).unwrap();
+    let input = utils::input::read(INPUT_FILE);
 
     let iters = 1;
 
-    let fns: Vec<(&'static str, fn(&str) -> i64)> = vec![("part1", part1), ("part2", part2)];
+    // part2_cloning takes seconds, so it's only checked against the others in the tests
+    let fns: Vec<(&'static str, fn(&str) -> i64)> = vec![
+        ("part1", part1),
+        ("part1_grid", part1_grid),
+        ("part2", part2),
+        ("part2_par", part2_par),
+    ];
 
     for (name, f) in &fns {
         println!("  {name}: {}", f(&input));
@@ -338,14 +612,48 @@ fn test_part2_example() {
     assert_eq!(part2(EXAMPLE_INPUT), 154);
 }
 
+#[test]
+fn test_dot_highlights_longest_path() {
+    let (graph, start, end) = junction_graph(EXAMPLE_INPUT, maze::two_way);
+    let (len, path) = Junctions::new(&graph, start, end).longest_hike().unwrap();
+    assert_eq!(len, 154);
+    assert_eq!(path.first(), Some(&graph.nodes[start]));
+    assert_eq!(path.last(), Some(&graph.nodes[end]));
+    let dot = dot(EXAMPLE_INPUT);
+    assert_eq!(dot.matches("penwidth").count(), path.len() - 1);
+}
+
+#[cfg(test)]
+fn arb_map() -> impl proptest::strategy::Strategy<Value = String> {
+    use proptest::prelude::*;
+    (any::<u64>(), 10usize..40)
+        .prop_map(|(seed, size)| generate(size, &mut rand::SeedableRng::seed_from_u64(seed)))
+}
+
+#[cfg(test)]
+proptest::proptest! {
+    #[test]
+    fn test_variants_agree(input in arb_map()) {
+        utils::assert_variants_agree(&input, &[("part1", part1), ("part1_grid", part1_grid)]);
+        utils::assert_variants_agree(
+            &input,
+            &[
+                ("part2", part2),
+                ("part2_par", part2_par),
+                ("part2_cloning", part2_cloning),
+            ],
+        );
+    }
+}
+
 #[test]
 fn test_part1_facit() {
-    let input = std::fs::read_to_string(INPUT_FILE).unwrap();
+    let input = utils::input::read(INPUT_FILE);
     assert_eq!(part1(&input), 2278);
 }
 
 #[test]
 fn test_part2_facit() {
-    let input = std::fs::read_to_string(INPUT_FILE).unwrap();
+    let input = utils::input::read(INPUT_FILE);
     assert_eq!(part2(&input), 6734);
 }
@@ -1,4 +1,8 @@
-use z3::ast::Ast;
+use std::collections::HashSet;
+
+use itertools::Itertools;
+use num::{rational::Ratio, BigRational, Signed, ToPrimitive, Zero};
+use rand::{rngs::StdRng, Rng};
 
 static INPUT_FILE: &str = "input/day24";
 #[allow(dead_code)]
@@ -18,6 +22,53 @@ pub fn part1_real(input: &str) -> i64 {
     part1(input, 200000000000000, 400000000000000)
 }
 pub fn part1(input: &str, min: i64, max: i64) -> i64 {
+    let stones = input
+        .lines()
+        .map(|l| {
+            let (p, v) = l.split_once(" @ ").unwrap();
+            let mut p = p.split(", ").map(|v| v.trim().parse::<i128>().unwrap());
+            let mut v = v.split(", ").map(|v| v.trim().parse::<i128>().unwrap());
+            (
+                (p.next().unwrap(), p.next().unwrap()),
+                (v.next().unwrap(), v.next().unwrap()),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let (min, max) = (Ratio::from(min as i128), Ratio::from(max as i128));
+    let within = |v: &Ratio<i128>| min <= *v && *v <= max;
+    stones
+        .iter()
+        .tuple_combinations()
+        .filter_map(|(a, b)| intersection(*a, *b))
+        .filter(|(x, y)| within(x) && within(y))
+        .count() as i64
+}
+
+type P2 = (i128, i128);
+
+/// Where the paths of two hailstones cross, if both get there strictly after time 0. Paths that
+/// are parallel, lie on top of each other, or belong to a hailstone that isn't moving have no
+/// single crossing point and give `None`.
+///
+/// Solves `pa + ta * va = pb + tb * vb` by Cramer's rule. With coordinates around 4e14 and
+/// velocities around 1e3 the numerators stay below 1e21, well within an i128.
+fn intersection((pa, va): (P2, P2), (pb, vb): (P2, P2)) -> Option<(Ratio<i128>, Ratio<i128>)> {
+    let det = vb.0 * va.1 - va.0 * vb.1;
+    if det == 0 {
+        return None;
+    }
+    let (dx, dy) = (pb.0 - pa.0, pb.1 - pa.1);
+    let ta = Ratio::new(vb.0 * dy - vb.1 * dx, det);
+    let tb = Ratio::new(va.0 * dy - va.1 * dx, det);
+    if !ta.is_positive() || !tb.is_positive() {
+        return None;
+    }
+    Some((ta * va.0 + pa.0, ta * va.1 + pa.1))
+}
+
+/// The first version, with `f64` slopes and intercepts.
+pub fn part1_float(input: &str, min: i64, max: i64) -> i64 {
     let stones = input
         .lines()
         .map(|l| {
@@ -35,7 +86,7 @@ pub fn part1(input: &str, min: i64, max: i64) -> i64 {
     for (ix, a) in stones.iter().enumerate() {
         for b in &stones[(ix + 1)..] {
             // println!("(a, b): {:?}", (a, b));
-            let Some((x, y)) = intersection(*a, *b) else {
+            let Some((x, y)) = intersection_float(*a, *b) else {
                 continue;
             };
             let min = min as f64;
@@ -62,7 +113,7 @@ fn slope_intercept(((x, y), (dx, dy)): L2) -> (f64, f64) {
     (m, b)
 }
 
-fn intersection(a: L2, b: L2) -> Option<V2> {
+fn intersection_float(a: L2, b: L2) -> Option<V2> {
     // Find how long it takes to intersect in general. I suppose I'm assuming that the
     // paths do in fact intersect?
     let (ma, ba) = slope_intercept(a);
@@ -111,10 +162,10 @@ fn intersection(a: L2, b: L2) -> Option<V2> {
     Some((x0, y0))
 }
 
-pub fn part2(input: &str) -> i64 {
-    // TODO: look into solving this without Z3
-    use z3::ast;
-    let stones = input
+type V3 = (i64, i64, i64);
+
+fn parse_stones(input: &str) -> Vec<(V3, V3)> {
+    input
         .lines()
         .map(|l| {
             let (p, v) = l.split_once(" @ ").unwrap();
@@ -125,70 +176,217 @@ pub fn part2(input: &str) -> i64 {
                 (v.next().unwrap(), v.next().unwrap(), v.next().unwrap()),
             )
         })
-        .collect::<Vec<_>>();
+        .collect()
+}
+
+/// Z3 when the `z3` feature is on, otherwise solved natively.
+pub fn part2(input: &str) -> i64 {
+    #[cfg(feature = "z3")]
+    return part2_z3(input);
+    #[cfg(not(feature = "z3"))]
+    part2_linear(input)
+}
+
+/// The rock `P + tV` hits hailstone `p + tv` when `(P - p) x (V - v) = 0`. The only nonlinear
+/// term in that is `P x V`, which is the same for every hailstone, so subtracting the equations
+/// for two hailstones gives three linear ones:
+///
+///   `P x (v1 - v2) + (p1 - p2) x V = p1 x v1 - p2 x v2`
+///
+/// Two pairs give six equations for the six unknowns, solved exactly with big rationals since the
+/// coefficients are way past what fits in an i64 once they're multiplied together.
+pub fn part2_linear(input: &str) -> i64 {
+    let stones = parse_stones(input);
+    let solution = (1..stones.len())
+        .tuple_combinations()
+        .find_map(|(a, b)| {
+            let mut rows = pair_equations(stones[0], stones[a]);
+            rows.extend(pair_equations(stones[0], stones[b]));
+            solve_linear(rows)
+        })
+        .expect("no three hailstones that pin down the rock");
+    let position = solution[..3].iter().fold(BigRational::zero(), |a, b| a + b);
+    assert!(position.is_integer(), "the rock starts at a fraction");
+    position.to_integer().to_i64().unwrap()
+}
+
+/// Rows of `[Px, Py, Pz, Vx, Vy, Vz, rhs]` from the pair of hailstones `a` and `b`.
+fn pair_equations((pa, va): (V3, V3), (pb, vb): (V3, V3)) -> Vec<[i128; 7]> {
+    let cross = |a: V3, b: V3| {
+        let (a, b) = (
+            (a.0 as i128, a.1 as i128, a.2 as i128),
+            (b.0 as i128, b.1 as i128, b.2 as i128),
+        );
+        (
+            a.1 * b.2 - a.2 * b.1,
+            a.2 * b.0 - a.0 * b.2,
+            a.0 * b.1 - a.1 * b.0,
+        )
+    };
+    let dv = (
+        (va.0 - vb.0) as i128,
+        (va.1 - vb.1) as i128,
+        (va.2 - vb.2) as i128,
+    );
+    let dp = (
+        (pa.0 - pb.0) as i128,
+        (pa.1 - pb.1) as i128,
+        (pa.2 - pb.2) as i128,
+    );
+    let (ca, cb) = (cross(pa, va), cross(pb, vb));
+    let c = (ca.0 - cb.0, ca.1 - cb.1, ca.2 - cb.2);
+    vec![
+        [0, dv.2, -dv.1, 0, -dp.2, dp.1, c.0],
+        [-dv.2, 0, dv.0, dp.2, 0, -dp.0, c.1],
+        [dv.1, -dv.0, 0, -dp.1, dp.0, 0, c.2],
+    ]
+}
+
+/// Gauss-Jordan elimination on an augmented matrix, or `None` if it's singular.
+fn solve_linear(rows: Vec<[i128; 7]>) -> Option<Vec<BigRational>> {
+    let mut m = rows
+        .iter()
+        .map(|row| row.map(|v| BigRational::from_integer(v.into())).to_vec())
+        .collect_vec();
+    let n = m.len();
+    for col in 0..n {
+        let pivot = (col..n).find(|row| !m[*row][col].is_zero())?;
+        m.swap(col, pivot);
+        let pivot = m[col].clone();
+        for (ix, row) in m.iter_mut().enumerate() {
+            if ix == col || row[col].is_zero() {
+                continue;
+            }
+            let factor = &row[col] / &pivot[col];
+            for (v, p) in row.iter_mut().zip(&pivot).skip(col) {
+                *v -= &factor * p;
+            }
+        }
+    }
+    Some((0..n).map(|ix| &m[ix][n] / &m[ix][ix]).collect())
+}
+
+/// The rock's unknowns along each axis: where it starts, and how fast it moves.
+const ROCK: [(&str, &str); 3] = [("x0", "dx"), ("y0", "dy"), ("z0", "dz")];
+
+/// What the rock has to do, for both `part2_z3` and `smt`: hit hailstone `n` at some time
+/// `tn > 0`, so `x0 + dx * tn = x + vx * tn` for hailstone `x @ vx`, and the same in y and z. One
+/// `(tn, [(x, vx), (y, vy), (z, vz)])` per hailstone, lined up with [`ROCK`].
+fn hit_constraints(stones: &[(V3, V3)]) -> Vec<(String, [(i64, i64); 3])> {
+    stones
+        .iter()
+        .enumerate()
+        .map(|(ix, &((x, y, z), (vx, vy, vz)))| {
+            (format!("t{}", ix + 1), [(x, vx), (y, vy), (z, vz)])
+        })
+        .collect()
+}
+
+/// The constraints `part2_z3` gives Z3, as a standalone SMT-LIB2 script in the shape of
+/// `day24.z3`. Negative numbers are written `(- 2)`, which is the standard form.
+pub fn smt(input: &str) -> String {
+    let int = |v: i64| match v {
+        0.. => v.to_string(),
+        _ => format!("(- {})", v.unsigned_abs()),
+    };
+    let mut lines = vec![
+        "(set-option :produce-models true)".to_string(),
+        "(set-logic QF_NIA)".to_string(),
+    ];
+    for v in ROCK.iter().map(|v| v.0).chain(ROCK.iter().map(|v| v.1)) {
+        lines.push(format!("(declare-const {v} Int)"));
+    }
+    for (t, axes) in hit_constraints(&parse_stones(input)) {
+        let [(x, vx), (y, vy), (z, vz)] = axes;
+        lines.push(String::new());
+        lines.push(format!("; {x}, {y}, {z} @ {vx}, {vy}, {vz}"));
+        lines.push(format!("(declare-const {t} Int)"));
+        lines.push(format!("(assert (> {t} 0))"));
+        for ((v0, dv0), (v, dv)) in ROCK.iter().zip(axes) {
+            lines.push(format!(
+                "(assert (= (+ {v0} (* {dv0} {t})) (+ {} (* {} {t}))))",
+                int(v),
+                int(dv)
+            ));
+        }
+    }
+    lines.push(String::new());
+    lines.push("(check-sat)".to_string());
+    lines.push("(get-value (x0 y0 z0))".to_string());
+    lines.join("\n") + "\n"
+}
+
+#[cfg(feature = "z3")]
+pub fn part2_z3(input: &str) -> i64 {
+    use z3::ast::{self, Ast};
+    let stones = parse_stones(input);
 
     let config = z3::Config::new();
     let ctx = z3::Context::new(&config);
-    let x0 = ast::Int::new_const(&ctx, "x0");
-    let y0 = ast::Int::new_const(&ctx, "y0");
-    let z0 = ast::Int::new_const(&ctx, "z0");
-
-    let dx0 = ast::Int::new_const(&ctx, "dx0");
-    let dy0 = ast::Int::new_const(&ctx, "dy0");
-    let dz0 = ast::Int::new_const(&ctx, "dz0");
+    let rock = ROCK.map(|(v0, dv0)| {
+        (
+            ast::Int::new_const(&ctx, v0),
+            ast::Int::new_const(&ctx, dv0),
+        )
+    });
 
     let solver = z3::Solver::new(&ctx);
-    for (ix, ((x, y, z), (dx, dy, dz))) in stones.iter().enumerate() {
-        let t = ast::Int::new_const(&ctx, ix as u32);
+    for (t, axes) in hit_constraints(&stones) {
+        let t = ast::Int::new_const(&ctx, t.as_str());
         solver.assert(&t.gt(&ast::Int::from_i64(&ctx, 0)));
-        for (v0, dv0, v, dv) in [(&x0, &dx0, x, dx), (&y0, &dy0, y, dy), (&z0, &dz0, z, dz)] {
+        for ((v0, dv0), (v, dv)) in rock.iter().zip(axes) {
             let l = v0 + (dv0 * &t);
-            let r = ast::Int::from_i64(&ctx, *v) + &t * ast::Int::from_i64(&ctx, *dv);
+            let r = ast::Int::from_i64(&ctx, v) + &t * ast::Int::from_i64(&ctx, dv);
             solver.assert(&l._eq(&r));
         }
     }
-    // println!("solver:\n{}", solver.to_string());
-
     solver.check();
-    // println!("solver.check_sat(): {:?}", solver.check());
     let model = solver.get_model().unwrap();
-    // println!("model: {:?}", model);
-
-    // (assert (= (+ x0 (* dx t1)) (+ 19 (* -2 t1))))
-    // (assert (= (+ y0 (* dy t1)) (+ 13 (* 1 t1))))
-    // (assert (= (+ z0 (* dz t1)) (+ 30 (* -2 t1))))
-
-    // (assert (= (+ x0 (* dx t2)) (+ 18 (* -1 t2))))
-    // (assert (= (+ y0 (* dy t2)) (+ 19 (* -1 t2))))
-    // (assert (= (+ z0 (* dz t2)) (+ 22 (* -2 t2))))
-
-    // (assert (= (+ x0 (* dx t3)) (+ 20 (* -2 t3))))
-    // (assert (= (+ y0 (* dy t3)) (+ 25 (* -2 t3))))
-    // (assert (= (+ z0 (* dz t3)) (+ 34 (* -4 t3))))
-
-    // (assert (= (+ x0 (* dx0 k!0)) (+ 19 (* k!0 (- 2)))))
-    // (assert (= (+ y0 (* dy0 k!0)) (+ 13 (* k!0 1))))
-    // (assert (= (+ z0 (* dz0 k!0)) (+ 30 (* k!0 (- 2)))))
-    // (assert (= (+ x0 (* dx0 k!1)) (+ 18 (* k!1 (- 1)))))
-    // (assert (= (+ y0 (* dy0 k!1)) (+ 19 (* k!1 (- 1)))))
-    // (assert (= (+ z0 (* dz0 k!1)) (+ 22 (* k!1 (- 2)))))
-    // (assert (= (+ x0 (* dx0 k!2)) (+ 20 (* k!2 (- 2)))))
-    // (assert (= (+ y0 (* dy0 k!2)) (+ 25 (* k!2 (- 2)))))
-    // (assert (= (+ z0 (* dz0 k!2)) (+ 34 (* k!2 (- 4)))))
-
-    let x0 = model.eval(&x0, true).unwrap().as_i64().unwrap();
-    let y0 = model.eval(&y0, true).unwrap().as_i64().unwrap();
-    let z0 = model.eval(&z0, true).unwrap().as_i64().unwrap();
-    // println!("(x0, y0, z0): {:?}", (x0, y0, z0));
+    let [x0, y0, z0] = rock
+        .each_ref()
+        .map(|(v0, _)| model.eval(v0, true).unwrap().as_i64().unwrap());
     x0 + y0 + z0
 }
 
+/// `size` random hailstones that a single rock thrown from around 2e14 can hit, each at a
+/// different time.
+pub fn generate(size: usize, rng: &mut StdRng) -> String {
+    generate_with_rock(size, rng).0
+}
+
+/// [`generate`], along with where the rock that hits every hailstone is thrown from.
+fn generate_with_rock(size: usize, rng: &mut StdRng) -> (String, V3) {
+    let p0 = [(); 3].map(|_| rng.gen_range(100_000_000_000_000i64..300_000_000_000_000));
+    let v0 = [(); 3].map(|_| rng.gen_range(-250i64..=250));
+    let mut times = HashSet::new();
+    let mut lines = vec![];
+    while lines.len() < size {
+        let t = rng.gen_range(1..1_000_000_000_000i64);
+        let v = [(); 3].map(|_| rng.gen_range(-300i64..=300));
+        if v == v0 || !times.insert(t) {
+            continue;
+        }
+        let p = [0, 1, 2].map(|ix| p0[ix] + t * (v0[ix] - v[ix]));
+        lines.push(format!(
+            "{}, {}, {} @ {}, {}, {}",
+            p[0], p[1], p[2], v[0], v[1], v[2]
+        ));
+    }
+    (lines.join("\n"), (p0[0], p0[1], p0[2]))
+}
+
 pub fn main(bench: bool) {
-    let input = std::fs::read_to_string(INPUT_FILE).unwrap();
+    let input = utils::input::read(INPUT_FILE);
 
     let iters = 1;
 
-    let fns: Vec<(&'static str, fn(&str) -> i64)> = vec![("part1", part1_real), ("part2", part2)];
+    let fns: Vec<(&'static str, fn(&str) -> i64)> = vec![
+        ("part1", part1_real),
+        ("part2", part2),
+        // part2 is part2_linear unless it's using Z3
+        #[cfg(feature = "z3")]
+        ("part2_linear", part2_linear),
+    ];
 
     for (name, f) in &fns {
         println!("  {name}: {}", f(&input));
@@ -222,60 +420,92 @@ fn test_part2_example() {
     assert_eq!(part2(EXAMPLE_INPUT), 47);
 }
 
+#[test]
+fn test_part2_generated() {
+    let mut rng = rand::SeedableRng::seed_from_u64(24);
+    for size in [3, 5, 20, 300] {
+        let (input, (x, y, z)) = generate_with_rock(size, &mut rng);
+        assert_eq!(part2_linear(&input), x + y + z, "{size} hailstones");
+        #[cfg(feature = "z3")]
+        assert_eq!(part2_z3(&input), x + y + z, "{size} hailstones");
+    }
+}
+
 #[test]
 fn test_part1_facit() {
-    let input = std::fs::read_to_string(INPUT_FILE).unwrap();
-    assert_eq!(part1_real(&input), 0);
+    let input = utils::input::read(INPUT_FILE);
+    assert_eq!(part1_real(&input), 19976);
 }
 
 #[test]
 fn test_part2_facit() {
-    let input = std::fs::read_to_string(INPUT_FILE).unwrap();
-    assert_eq!(part2(&input), 0);
+    let input = utils::input::read(INPUT_FILE);
+    assert_eq!(part2(&input), 849377770236905);
 }
 
 #[test]
 fn test_intersection() {
-    assert_eq!(
-        intersection(((19.0, 13.0), (-2.0, 1.0)), ((18.0, 19.0), (-1.0, -1.0))),
-        Some((14.333333333333334, 15.333333333333332))
-    );
-
-    assert_eq!(
-        intersection(((19.0, 13.0), (-2.0, 1.0)), ((20.0, 25.0), (-2.0, -2.0))),
-        Some((11.666666666666666, 16.666666666666668))
-    );
-
-    assert_eq!(
-        intersection(((19.0, 13.0), (-2.0, 1.0)), ((12.0, 31.0), (-1.0, -2.0))),
-        Some((6.2, 19.4))
-    );
-    assert_eq!(
-        intersection(((19.0, 13.0), (-2.0, 1.0)), ((20.0, 19.0), (1.0, -5.0))),
-        None
+    let r = |n: i128, d: i128| Ratio::new(n, d);
+    let (a, b, c) = (
+        ((19, 13), (-2, 1)),
+        ((18, 19), (-1, -1)),
+        ((20, 25), (-2, -2)),
     );
+    let (d, e) = (((12, 31), (-1, -2)), ((20, 19), (1, -5)));
+    assert_eq!(intersection(a, b), Some((r(43, 3), r(46, 3))));
+    assert_eq!(intersection(a, c), Some((r(35, 3), r(50, 3))));
+    assert_eq!(intersection(a, d), Some((r(31, 5), r(97, 5))));
+    assert_eq!(intersection(a, e), None);
+    assert_eq!(intersection(b, c), None);
+    assert_eq!(intersection(b, d), Some((r(-6, 1), r(-5, 1))));
+    assert_eq!(intersection(b, e), None);
+    assert_eq!(intersection(c, d), Some((r(-2, 1), r(3, 1))));
+    assert_eq!(intersection(c, e), None);
+    assert_eq!(intersection(d, e), None);
+
+    // parallel, and on top of each other
+    assert_eq!(intersection(((0, 0), (1, 1)), ((0, 1), (1, 1))), None);
+    assert_eq!(intersection(((0, 0), (1, 1)), ((5, 5), (2, 2))), None);
+    // a hailstone that isn't moving
+    assert_eq!(intersection(((0, 0), (0, 0)), ((-1, 0), (1, 0))), None);
+    // vertical paths have no slope
     assert_eq!(
-        intersection(((18.0, 19.0), (-1.0, -1.0)), ((20.0, 25.0), (-2.0, -2.0))),
-        None
+        intersection(((0, 0), (0, 1)), ((-5, 3), (1, 0))),
+        Some((r(0, 1), r(3, 1)))
     );
+    // crossing right where one of them is now isn't in the future
+    assert_eq!(intersection(((0, 0), (1, 0)), ((0, -2), (0, 1))), None);
+    // nearly parallel, far from the origin
     assert_eq!(
-        intersection(((18.0, 19.0), (-1.0, -1.0)), ((12.0, 31.0), (-1.0, -2.0))),
-        Some((-6.0, -5.0))
-    );
-    assert_eq!(
-        intersection(((18.0, 19.0), (-1.0, -1.0)), ((20.0, 19.0), (1.0, -5.0))),
-        None
-    );
-    assert_eq!(
-        intersection(((20.0, 25.0), (-2.0, -2.0)), ((12.0, 31.0), (-1.0, -2.0))),
-        Some((-2.0, 3.0))
+        intersection(
+            ((200000000000000, 300000000000000), (-999, 1000)),
+            ((199999999999983, 300000000000000), (-998, 999))
+        ),
+        Some((r(199999983033983, 1), r(300000016983000, 1)))
     );
+}
+
+#[test]
+fn test_smt() {
+    let smt = smt(EXAMPLE_INPUT);
+    assert!(smt.starts_with("(set-option :produce-models true)\n"));
+    assert_eq!(smt.matches("(declare-const t").count(), 5);
+    assert_eq!(smt.matches("(assert (=").count(), 15);
+    assert!(smt.contains("; 19, 13, 30 @ -2, 1, -2\n(declare-const t1 Int)\n(assert (> t1 0))\n"));
+    assert!(smt.contains("(assert (= (+ x0 (* dx t1)) (+ 19 (* (- 2) t1))))\n"));
+    assert!(smt.contains("(assert (= (+ y0 (* dy t5)) (+ 19 (* (- 5) t5))))\n"));
+    assert_eq!(smt.matches('(').count(), smt.matches(')').count());
+}
+
+#[test]
+fn test_part1_variants_agree() {
+    let input = utils::input::read(INPUT_FILE);
     assert_eq!(
-        intersection(((20.0, 25.0), (-2.0, -2.0)), ((20.0, 19.0), (1.0, -5.0))),
-        None
+        part1_real(&input),
+        part1_float(&input, 200000000000000, 400000000000000)
     );
     assert_eq!(
-        intersection(((12.0, 31.0), (-1.0, -2.0)), ((20.0, 19.0), (1.0, -5.0))),
-        None
+        part1_example(EXAMPLE_INPUT),
+        part1_float(EXAMPLE_INPUT, 7, 27)
     );
 }
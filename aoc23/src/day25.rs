@@ -1,4 +1,11 @@
-use std::collections::{HashMap, HashSet, VecDeque};
+use std::collections::{HashMap, HashSet};
+
+use itertools::Itertools;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng};
+use utils::{
+    dot::{Dot, HIGHLIGHT},
+    gen, mincut,
+};
 
 static INPUT_FILE: &str = "input/day25";
 #[allow(dead_code)]
@@ -16,57 +23,54 @@ lsr: lhk
 rzs: qnr cmg lsr rsh
 frs: qnr lhk lsr"#;
 
-pub fn part1(input: &str) -> i64 {
-    let mut flat_edges = Vec::<(&str, &str)>::new();
-    let mut edges = HashMap::<&str, HashSet<&str>>::new();
-    let mut nodes = HashSet::<&str>::new();
+/// The wires that split the components in two when disconnected, and how many components end up
+/// on each side.
+pub fn split(input: &str) -> (Vec<(&str, &str)>, usize, usize) {
+    let mut ids = HashMap::<&str, usize>::new();
+    let mut wires = Vec::<(&str, &str)>::new();
     for line in input.lines() {
         let (src, tail) = line.split_once(": ").unwrap();
-        nodes.insert(src);
         for tar in tail.split_whitespace() {
-            edges.entry(src).or_default().insert(tar);
-            edges.entry(tar).or_default().insert(src);
-            flat_edges.push((src, tar));
-            nodes.insert(tar);
-            // println!("{src} --- {tar};");
+            wires.push((src, tar));
+        }
+    }
+    for (a, b) in &wires {
+        for name in [a, b] {
+            let next = ids.len();
+            ids.entry(name).or_insert(next);
         }
     }
-    // return -1;
+    let edges = wires.iter().map(|(a, b)| (ids[a], ids[b], 1)).collect_vec();
+    let cut = mincut::stoer_wagner(ids.len(), &edges).expect("there's nothing to split");
+    assert_eq!(cut.weight, 3, "the smallest cut isn't three wires");
+    let side = cut.side.into_iter().collect::<HashSet<_>>();
+    let cut_wires = wires
+        .into_iter()
+        .filter(|(a, b)| side.contains(&ids[a]) != side.contains(&ids[b]))
+        .collect_vec();
+    (cut_wires, side.len(), ids.len() - side.len())
+}
 
-    // Ok, so. this obviously doesn't finish fast enough if you brute force it. you can supposedly
-    // do some fancy algorithms. so what i did was just run it through graphviz. and u can very
-    // clearly see three edges connecting two big clusters.
-    // TODO: do the grownup version: https://en.wikipedia.org/wiki/Karger%27s_algorithm
-    let edge_1 = ("vgk", "mbq");
-    let edge_2 = ("nmv", "thl");
-    let edge_3 = ("fzb", "fxr");
-    edges.get_mut(edge_1.0).unwrap().remove(edge_1.1);
-    edges.get_mut(edge_1.1).unwrap().remove(edge_1.0);
-    edges.get_mut(edge_2.0).unwrap().remove(edge_2.1);
-    edges.get_mut(edge_2.1).unwrap().remove(edge_2.0);
-    edges.get_mut(edge_3.0).unwrap().remove(edge_3.1);
-    edges.get_mut(edge_3.1).unwrap().remove(edge_3.0);
+pub fn part1(input: &str) -> i64 {
+    let (_, a, b) = split(input);
+    (a * b) as i64
+}
 
-    let mut seen = HashSet::new();
-    let mut q = VecDeque::new();
-    q.push_back(nodes.iter().next().unwrap());
-    while let Some(node) = q.pop_front() {
-        if !seen.insert(node) {
-            continue;
-        }
-        for next in edges.get(node).into_iter().flatten() {
-            q.push_back(next);
+/// The wiring diagram as an undirected DOT graph, with the cut wires highlighted.
+pub fn dot(input: &str) -> String {
+    let (cut, _, _) = split(input);
+    let mut dot = Dot::graph("day25");
+    for line in input.lines() {
+        let (src, tail) = line.split_once(": ").unwrap();
+        for tar in tail.split_whitespace() {
+            if cut.contains(&(src, tar)) {
+                dot.edge(src, tar, HIGHLIGHT);
+            } else {
+                dot.edge(src, tar, &[]);
+            }
         }
     }
-    let is_split = seen.len() != nodes.len();
-    if is_split {
-        // println!("edge_1: {:?}", edge_1);
-        // println!("edge_2: {:?}", edge_2);
-        // println!("edge_3: {:?}", edge_3);
-        return (nodes.len() as i64 - seen.len() as i64) * seen.len() as i64;
-    }
-
-    panic!("graph is not split :(")
+    dot.to_string()
 }
 
 pub fn part2(input: &str) -> i64 {
@@ -74,8 +78,38 @@ pub fn part2(input: &str) -> i64 {
     todo!()
 }
 
+/// Two random clusters of `size` components each, where every component is wired to at least
+/// four others in its cluster, joined by just three wires.
+pub fn generate(size: usize, rng: &mut StdRng) -> String {
+    let size = size.max(5);
+    let names = gen::distinct_words(rng, 2 * size, 3, gen::LOWERCASE, &[]);
+    let mut wires = HashMap::<&str, Vec<&str>>::new();
+    for cluster in names.chunks(size) {
+        for (ix, name) in cluster.iter().enumerate() {
+            let mut earlier = cluster[..ix].iter().collect_vec();
+            earlier.shuffle(rng);
+            for other in earlier.into_iter().take(4) {
+                wires.entry(name).or_default().push(other);
+            }
+        }
+    }
+    let mut cut = HashSet::new();
+    while cut.len() < 3 {
+        let (a, b) = (rng.gen_range(0..size), rng.gen_range(size..2 * size));
+        if cut.insert((a, b)) {
+            wires.entry(&names[a]).or_default().push(&names[b]);
+        }
+    }
+    let mut lines = wires
+        .into_iter()
+        .map(|(name, others)| format!("{name}: {}", others.join(" ")))
+        .collect_vec();
+    lines.sort();
+    lines.join("\n")
+}
+
 pub fn main(bench: bool) {
-    let input = std::fs::read_to_string(INPUT_FILE).unwrap();
+    let input = utils::input::read(INPUT_FILE);
 
     let iters = 10;
 
@@ -84,6 +118,9 @@ pub fn main(bench: bool) {
     for (name, f) in &fns {
         println!("  {name}: {}", f(&input));
     }
+    let (cut, a, b) = split(&input);
+    let cut = cut.iter().map(|(a, b)| format!("{a}/{b}")).join(", ");
+    println!("  cutting {cut} leaves {a} and {b} components");
     println!("");
     if bench {
         for (name, f) in &fns {
@@ -103,8 +140,29 @@ pub fn main(bench: bool) {
     }
 }
 
+#[test]
+fn test_dot_highlights_cut() {
+    let input = utils::input::read(INPUT_FILE);
+    assert_eq!(dot(&input).matches("penwidth").count(), 3);
+}
+
+#[test]
+fn test_part1_example() {
+    assert_eq!(part1(EXAMPLE_INPUT), 54);
+    let (mut cut, a, b) = split(EXAMPLE_INPUT);
+    cut.sort();
+    assert_eq!(cut, [("cmg", "bvb"), ("jqt", "nvd"), ("pzl", "hfx")]);
+    assert_eq!((a.min(b), a.max(b)), (6, 9));
+}
+
+#[test]
+fn test_generate() {
+    let mut rng = rand::SeedableRng::seed_from_u64(25);
+    assert_eq!(part1(&generate(40, &mut rng)), 40 * 40);
+}
+
 #[test]
 fn test_part1_facit() {
-    let input = std::fs::read_to_string(INPUT_FILE).unwrap();
+    let input = utils::input::read(INPUT_FILE);
     assert_eq!(part1(&input), 600369);
 }
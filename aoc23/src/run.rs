@@ -1,4 +1,40 @@
+use std::io::Write;
+
+use itertools::Itertools;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use utils::{
+    opts::Opts,
+    trace::{self, Level},
+};
+
 pub fn main(args: &[String], bench: bool) {
+    let valued = [
+        "--dot",
+        "--emit-smt",
+        "--vcd",
+        "--route",
+        "--dump-composed",
+        "--presses",
+        "--trace",
+        "--trace-file",
+    ];
+    let opts = match Opts::parse(args, &valued) {
+        Ok(opts) => opts,
+        Err(e) => {
+            eprintln!("{e}");
+            return;
+        }
+    };
+    if let Some(level) = opts.get("--trace") {
+        let Some(level) = Level::parse(level) else {
+            eprintln!("unknown trace level: {level} (expected info, debug or trace)");
+            return;
+        };
+        if let Err(e) = trace::enable(level, opts.get("--trace-file")) {
+            eprintln!("can't open trace file: {e}");
+            return;
+        }
+    }
     let fns: Vec<(&'static str, fn(bool))> = vec![
         ("day01", crate::day01::main),
         ("day02", crate::day02::main),
@@ -27,12 +63,160 @@ pub fn main(args: &[String], bench: bool) {
         ("day25", crate::day25::main),
         // [NEXT DAY]
     ];
-    for t in args {
+    let presses = match opts.get("--presses").map(str::parse) {
+        None => 1,
+        Some(Ok(presses)) => presses,
+        Some(Err(e)) => {
+            eprintln!("bad --presses: {e}");
+            return;
+        }
+    };
+    // (flag, day, what gets written to the flag's path)
+    let outputs: Vec<(&'static str, &'static str, Box<dyn Fn(&str) -> String>)> = vec![
+        ("--dot", "day19", Box::new(crate::day19::dot)),
+        ("--dot", "day20", Box::new(crate::day20::dot)),
+        ("--dot", "day23", Box::new(crate::day23::dot)),
+        ("--dot", "day25", Box::new(crate::day25::dot)),
+        ("--emit-smt", "day24", Box::new(crate::day24::smt)),
+        (
+            "--vcd",
+            "day20",
+            Box::new(move |input| crate::day20::vcd(input, presses)),
+        ),
+        ("--route", "day17", Box::new(crate::day17::render)),
+        (
+            "--dump-composed",
+            "day05",
+            Box::new(crate::day05::dump_composed),
+        ),
+    ];
+    let selected = |t: &str, day: &str| t == "all" || t == day;
+    for t in &opts.targets {
         for (name, f) in &fns {
-            if *t == "all" || t == name {
+            if selected(t, name) {
                 println!("\n{name}:");
+                trace::set_scope(name);
                 f(bench);
             }
         }
+        for flag in outputs.iter().map(|(flag, _, _)| *flag).dedup() {
+            let Some(path) = opts.get(flag) else {
+                continue;
+            };
+            let days = outputs
+                .iter()
+                .filter(|(f, name, _)| *f == flag && selected(t, name))
+                .collect::<Vec<_>>();
+            if days.is_empty() && t != "all" {
+                eprintln!("no {flag} output for {t}");
+            }
+            // with more than one day to write, each gets its own file
+            let flag_days = outputs
+                .iter()
+                .filter(|(f, name, _)| *f == flag && opts.targets.iter().any(|t| selected(t, name)))
+                .count();
+            for (_, name, output) in days {
+                let path = if flag_days > 1 {
+                    per_day_path(path, name)
+                } else {
+                    path.to_string()
+                };
+                let input = utils::input::read(&format!("input/{name}"));
+                std::fs::write(&path, output(&input)).unwrap();
+                println!("  wrote {path}");
+            }
+        }
     }
+    trace::disable();
+}
+
+/// `path` with `day` put in before the extension, so `graph.dot` becomes `graph.day19.dot`.
+fn per_day_path(path: &str, day: &str) -> String {
+    let file_start = path.rfind('/').map_or(0, |ix| ix + 1);
+    match path[file_start..].rfind('.') {
+        Some(dot) if dot > 0 => {
+            let (stem, ext) = path.split_at(file_start + dot);
+            format!("{stem}.{day}{ext}")
+        }
+        _ => format!("{path}.{day}"),
+    }
+}
+
+#[test]
+fn test_per_day_path() {
+    assert_eq!(per_day_path("graph.dot", "day19"), "graph.day19.dot");
+    assert_eq!(per_day_path("out/graph", "day19"), "out/graph.day19");
+    assert_eq!(per_day_path("a.b/graph", "day19"), "a.b/graph.day19");
+    assert_eq!(per_day_path(".dot", "day19"), ".dot.day19");
+}
+
+/// Prints a random input for one day to stdout, e.g. `aoc gen 23 day16 --size 500 --seed 7`.
+/// What `--size` means is up to each day's generator.
+pub fn gen(args: &[String]) {
+    let opts = match Opts::parse(args, &["--size", "--seed"]) {
+        Ok(opts) => opts,
+        Err(e) => {
+            eprintln!("{e}");
+            return;
+        }
+    };
+    // (day, generator, default size)
+    let gens: Vec<(&'static str, fn(usize, &mut StdRng) -> String, usize)> = vec![
+        ("day01", crate::day01::generate, 1000),
+        ("day02", crate::day02::generate, 100),
+        ("day03", crate::day03::generate, 140),
+        ("day04", crate::day04::generate, 200),
+        ("day05", crate::day05::generate, u32::MAX as usize),
+        ("day06", crate::day06::generate, 99),
+        ("day07", crate::day07::generate, 1000),
+        ("day08", crate::day08::generate, 250),
+        ("day09", crate::day09::generate, 200),
+        ("day10", crate::day10::generate, 140),
+        ("day11", crate::day11::generate, 140),
+        ("day12", crate::day12::generate, 1000),
+        ("day13", crate::day13::generate, 100),
+        ("day14", crate::day14::generate, 100),
+        ("day15", crate::day15::generate, 4000),
+        ("day16", crate::day16::generate, 110),
+        ("day17", crate::day17::generate, 141),
+        ("day18", crate::day18::generate, 150),
+        ("day19", crate::day19::generate, 500),
+        ("day20", crate::day20::generate, 4),
+        ("day21", crate::day21::generate, 131),
+        ("day22", crate::day22::generate, 1200),
+        ("day23", crate::day23::generate, 141),
+        ("day24", crate::day24::generate, 300),
+        ("day25", crate::day25::generate, 750),
+    ];
+    let [day] = opts.targets.as_slice() else {
+        eprintln!("expected exactly one day to generate input for");
+        return;
+    };
+    let Some((_, generate, default_size)) = gens.iter().find(|(name, ..)| name == day) else {
+        eprintln!("no input generator for {day}");
+        return;
+    };
+    let size = match opts.get("--size").map(str::parse) {
+        None => *default_size,
+        Some(Ok(size)) => size,
+        Some(Err(e)) => {
+            eprintln!("bad --size: {e}");
+            return;
+        }
+    };
+    let seed = match opts.get("--seed").map(str::parse) {
+        None => {
+            let seed = rand::thread_rng().gen();
+            eprintln!("seed: {seed}");
+            seed
+        }
+        Some(Ok(seed)) => seed,
+        Some(Err(e)) => {
+            eprintln!("bad --seed: {e}");
+            return;
+        }
+    };
+    let mut rng = StdRng::seed_from_u64(seed);
+    // don't panic when piped into something like `head`
+    let _ = writeln!(std::io::stdout(), "{}", generate(size, &mut rng));
 }
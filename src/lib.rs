@@ -0,0 +1,285 @@
+//! Every solution behind one signature, for embedding the solvers in other tools.
+//!
+//! ```no_run
+//! let input = std::fs::read_to_string("aoc23/input/day01").unwrap();
+//! println!("{}", aoc::solve(2023, 1, 2, &input).unwrap());
+//! ```
+//!
+//! The days themselves take whatever arguments and return whatever types suited them (day21's
+//! step count, day24's test area, day03's `i32`s). The catalog pins those down to what the real
+//! puzzle asks for.
+use std::{
+    fmt::{self, Display},
+    panic::{self, AssertUnwindSafe},
+};
+
+use utils::assume::{self, Assumption, Violation};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Answer {
+    Int(i64),
+    Text(String),
+}
+impl Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Answer::Int(v) => write!(f, "{v}"),
+            Answer::Text(v) => write!(f, "{v}"),
+        }
+    }
+}
+impl From<i64> for Answer {
+    fn from(v: i64) -> Self {
+        Answer::Int(v)
+    }
+}
+impl From<i32> for Answer {
+    fn from(v: i32) -> Self {
+        Answer::Int(v as i64)
+    }
+}
+impl From<String> for Answer {
+    fn from(v: String) -> Self {
+        Answer::Text(v)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// Nothing in the catalog for that year, day and part.
+    NotFound { year: u32, day: u32, part: u32 },
+    /// The solution panicked, usually because the input isn't shaped like it expected.
+    Panicked { id: SolutionId, message: String },
+    /// The input breaks something the solution relies on, so it wasn't run.
+    Assumption {
+        id: SolutionId,
+        violation: Violation,
+    },
+}
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::NotFound { year, day, part } => {
+                write!(f, "no solution for {year} day {day} part {part}")
+            }
+            Error::Panicked { id, message } => write!(f, "{id} panicked: {message}"),
+            Error::Assumption { id, violation } => write!(f, "{id}: {violation}"),
+        }
+    }
+}
+impl std::error::Error for Error {}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SolutionId {
+    pub year: u32,
+    pub day: u32,
+    pub part: u32,
+}
+impl Display for SolutionId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} day {:02} part {}", self.year, self.day, self.part)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Solution {
+    pub id: SolutionId,
+    run: fn(&str) -> Answer,
+    assumptions: &'static [Assumption],
+    variants: Vec<(&'static str, &'static [Assumption])>,
+}
+impl Solution {
+    /// Checks the input against [`Solution::assumptions`] and then runs the solution, turning a
+    /// panic in either into [`Error::Panicked`].
+    pub fn solve(&self, input: &str) -> Result<Answer> {
+        let id = self.id;
+        let run = || {
+            assume::check(self.assumptions, id.part, input)
+                .map(|_| (self.run)(input))
+                .map_err(|violation| Error::Assumption { id, violation })
+        };
+        panic::catch_unwind(AssertUnwindSafe(run)).unwrap_or_else(|e| {
+            let message = match (e.downcast_ref::<&str>(), e.downcast_ref::<String>()) {
+                (Some(s), _) => s.to_string(),
+                (_, Some(s)) => s.clone(),
+                _ => "unknown panic".to_string(),
+            };
+            Err(Error::Panicked { id, message })
+        })
+    }
+
+    /// What the solution relies on about its input beyond what the puzzle promises.
+    pub fn assumptions(&self) -> impl Iterator<Item = &'static Assumption> + '_ {
+        self.assumptions.iter().filter(|a| a.part == self.id.part)
+    }
+
+    /// What the day's other implementations of this part rely on, by name, like a quicker one
+    /// that only works on inputs shaped like the real ones. [`Solution::solve`] doesn't use
+    /// them, so it doesn't check these.
+    pub fn variant_assumptions(
+        &self,
+    ) -> impl Iterator<Item = (&'static str, &'static Assumption)> + '_ {
+        self.variants.iter().flat_map(move |(name, assumptions)| {
+            assumptions
+                .iter()
+                .filter(move |a| a.part == self.id.part)
+                .map(move |a| (*name, a))
+        })
+    }
+}
+
+fn assumptions(year: u32, day: u32) -> &'static [Assumption] {
+    match (year, day) {
+        (2023, 20) => aoc23::day20::ASSUMPTIONS,
+        _ => &[],
+    }
+}
+
+fn variant_assumptions(year: u32, day: u32) -> Vec<(&'static str, &'static [Assumption])> {
+    match (year, day) {
+        (2023, 8) => vec![("part2_lcm", aoc23::day08::LCM_ASSUMPTIONS)],
+        (2023, 21) => vec![("part2_geometric", aoc23::day21::GEOMETRIC_ASSUMPTIONS)],
+        _ => vec![],
+    }
+}
+
+/// Every solved part, ordered by year, day and part.
+pub fn catalog() -> Vec<Solution> {
+    let table: Vec<(u32, u32, u32, fn(&str) -> Answer)> = vec![
+        (2022, 1, 1, |i| aoc22::day01::part1(i).into()),
+        (2022, 1, 2, |i| aoc22::day01::part2(i).into()),
+        (2022, 2, 1, |i| aoc22::day02::part1(i).into()),
+        (2022, 2, 2, |i| aoc22::day02::part2(i).into()),
+        (2022, 3, 1, |i| aoc22::day03::part1(i).into()),
+        (2022, 3, 2, |i| aoc22::day03::part2(i).into()),
+        (2023, 1, 1, |i| aoc23::day01::part1(i).into()),
+        (2023, 1, 2, |i| {
+            aoc23::day01::part2_no_regex_bidir_add_directly_byte_lines_nz(i).into()
+        }),
+        (2023, 2, 1, |i| aoc23::day02::part1(i).into()),
+        (2023, 2, 2, |i| aoc23::day02::part2(i).into()),
+        (2023, 3, 1, |i| aoc23::day03::part1(i).into()),
+        (2023, 3, 2, |i| aoc23::day03::part2(i).into()),
+        (2023, 4, 1, |i| aoc23::day04::part1(i).into()),
+        (2023, 4, 2, |i| aoc23::day04::part2(i).into()),
+        (2023, 5, 1, |i| aoc23::day05::part1(i).into()),
+        (2023, 5, 2, |i| aoc23::day05::part2(i).into()),
+        (2023, 6, 1, |i| aoc23::day06::part1(i).into()),
+        (2023, 6, 2, |i| aoc23::day06::part2_math(i).into()),
+        (2023, 7, 1, |i| aoc23::day07::part1(i).into()),
+        (2023, 7, 2, |i| aoc23::day07::part2(i).into()),
+        (2023, 8, 1, |i| aoc23::day08::part1(i).into()),
+        (2023, 8, 2, |i| aoc23::day08::part2(i).into()),
+        (2023, 9, 1, |i| aoc23::day09::part1(i).into()),
+        (2023, 9, 2, |i| aoc23::day09::part2(i).into()),
+        (2023, 10, 1, |i| aoc23::day10::part1(i).into()),
+        (2023, 10, 2, |i| aoc23::day10::part2(i).into()),
+        (2023, 11, 1, |i| aoc23::day11::part1(i).into()),
+        (2023, 11, 2, |i| aoc23::day11::part2(i).into()),
+        (2023, 12, 1, |i| aoc23::day12::part1(i).into()),
+        (2023, 12, 2, |i| aoc23::day12::part2(i).into()),
+        (2023, 13, 1, |i| aoc23::day13::part1(i).into()),
+        (2023, 13, 2, |i| aoc23::day13::part2(i).into()),
+        (2023, 14, 1, |i| aoc23::day14::part1(i).into()),
+        (2023, 14, 2, |i| aoc23::day14::part2(i).into()),
+        (2023, 15, 1, |i| aoc23::day15::part1(i).into()),
+        (2023, 15, 2, |i| aoc23::day15::part2(i).into()),
+        (2023, 16, 1, |i| aoc23::day16::part1(i).into()),
+        (2023, 16, 2, |i| aoc23::day16::part2(i).into()),
+        (2023, 17, 1, |i| aoc23::day17::part1(i).into()),
+        (2023, 17, 2, |i| aoc23::day17::part2(i).into()),
+        (2023, 18, 1, |i| aoc23::day18::part1(i).into()),
+        (2023, 18, 2, |i| aoc23::day18::part2(i).into()),
+        (2023, 19, 1, |i| aoc23::day19::part1(i).into()),
+        (2023, 19, 2, |i| aoc23::day19::part2(i).into()),
+        (2023, 20, 1, |i| aoc23::day20::part1(i).into()),
+        (2023, 20, 2, |i| aoc23::day20::part2(i).into()),
+        (2023, 21, 1, |i| aoc23::day21::part1_real(i).into()),
+        (2023, 21, 2, |i| aoc23::day21::part2(i).into()),
+        (2023, 22, 1, |i| aoc23::day22::part1(i).into()),
+        (2023, 22, 2, |i| aoc23::day22::part2(i).into()),
+        (2023, 23, 1, |i| aoc23::day23::part1(i).into()),
+        (2023, 23, 2, |i| aoc23::day23::part2(i).into()),
+        (2023, 24, 1, |i| aoc23::day24::part1_real(i).into()),
+        (2023, 24, 2, |i| aoc23::day24::part2(i).into()),
+        (2023, 25, 1, |i| aoc23::day25::part1(i).into()),
+    ];
+    table
+        .into_iter()
+        .map(|(year, day, part, run)| Solution {
+            id: SolutionId { year, day, part },
+            run,
+            assumptions: assumptions(year, day),
+            variants: variant_assumptions(year, day),
+        })
+        .collect()
+}
+
+/// Looks up one solution. `year` is the full year, like 2023.
+pub fn find(year: u32, day: u32, part: u32) -> Result<Solution> {
+    let id = SolutionId { year, day, part };
+    catalog()
+        .into_iter()
+        .find(|s| s.id == id)
+        .ok_or(Error::NotFound { year, day, part })
+}
+
+/// Solves one part of one day for `input`, e.g. `aoc::solve(2023, 1, 2, &input)`.
+pub fn solve(year: u32, day: u32, part: u32, input: &str) -> Result<Answer> {
+    find(year, day, part)?.solve(input)
+}
+
+#[test]
+fn test_solve_example() {
+    let input = "1abc2\npqr3stu8vwx\na1b2c3d4e5f\ntreb7uchet";
+    assert_eq!(solve(2023, 1, 1, input), Ok(Answer::Int(142)));
+    assert_eq!(solve(2023, 1, 2, "two1nine").unwrap().to_string(), "29");
+}
+
+#[test]
+fn test_solve_errors() {
+    assert_eq!(
+        solve(2023, 26, 1, ""),
+        Err(Error::NotFound {
+            year: 2023,
+            day: 26,
+            part: 1
+        })
+    );
+    let err = solve(2023, 24, 1, "not a hailstone").unwrap_err();
+    assert!(matches!(err, Error::Panicked { id, .. } if id.day == 24));
+    let err = solve(
+        2023,
+        20,
+        2,
+        "broadcaster -> a\n%a -> inv, con\n&inv -> b\n%b -> con\n&con -> rx",
+    );
+    assert_eq!(
+        err.unwrap_err().to_string(),
+        "2023 day 20 part 2: assumption `lcm_shortcut` doesn't hold: the sub-circuits behind \
+        %a and %b share %a"
+    );
+}
+
+#[test]
+fn test_catalog() {
+    let catalog = catalog();
+    let ids = catalog.iter().map(|s| s.id).collect::<Vec<_>>();
+    let mut sorted = ids.clone();
+    sorted.sort();
+    sorted.dedup();
+    assert_eq!(ids, sorted);
+    // day 25 only has the one part
+    assert_eq!(ids.iter().filter(|id| id.year == 2023).count(), 49);
+
+    let day21 = find(2023, 21, 2).unwrap();
+    assert_eq!(day21.assumptions().count(), 0);
+    let variants = day21
+        .variant_assumptions()
+        .map(|(variant, a)| (variant, a.name))
+        .collect::<Vec<_>>();
+    assert_eq!(variants[0], ("part2_geometric", "square_grid"));
+    assert_eq!(find(2023, 21, 1).unwrap().variant_assumptions().count(), 0);
+}
@@ -1,4 +1,9 @@
-static USAGE: &str = "usage: aoc <22|23> [target...]";
+use std::{io::ErrorKind, panic::AssertUnwindSafe, time::Instant};
+
+use aoc::{Solution, SolutionId};
+use utils::{input, opts::Opts};
+
+static USAGE: &str = "usage: aoc <22|23> [target...] [--dot <file>] [--emit-smt <file>]\n       [--vcd <file> [--presses <n>]] [--route <file>] [--dump-composed <file>]\n       [--trace <info|debug|trace>] [--trace-file <file>]\n       aoc gen 23 <day> [--size <n>] [--seed <n>]\n       aoc verify [22|23] [dayNN...] [--all-inputs]\n       aoc assumptions [22|23] [dayNN...]";
 
 fn main() {
     let mut args = std::env::args().skip(1);
@@ -6,6 +11,23 @@ fn main() {
         eprintln!("{}", USAGE);
         return;
     };
+    if year == "gen" {
+        let gens: Vec<(&'static str, fn(&[String]))> = vec![("23", aoc23::run::gen)];
+        let year = args.next().unwrap_or_default();
+        match gens.into_iter().find(|(n, _)| *n == year) {
+            Some((_, f)) => f(&args.collect::<Vec<_>>()),
+            None => eprintln!("no input generators for year: {year}\n{USAGE}"),
+        }
+        return;
+    }
+    if year == "assumptions" {
+        assumptions(&args.collect::<Vec<_>>());
+        return;
+    }
+    if year == "verify" {
+        let ok = verify(&args.collect::<Vec<_>>());
+        std::process::exit(if ok { 0 } else { 1 });
+    }
     let fns: Vec<(&'static str, fn(&[String], bool))> =
         vec![("23", aoc23::run::main), ("22", aoc22::run::main)];
     let Some((name, f)) = fns.into_iter().find(|(n, _)| *n == year) else {
@@ -23,3 +45,138 @@ fn main() {
     f(&target, false);
     eprintln!("{}", USAGE);
 }
+
+/// Runs the catalog against `aoc{yy}/input/dayNN`, checking answers wherever an `.answers` file
+/// says what they should be. Only the default input unless `--all-inputs` is passed.
+fn verify(args: &[String]) -> bool {
+    let opts = match Opts::parse(args, &[]) {
+        Ok(opts) => opts,
+        Err(e) => {
+            eprintln!("{e}\n{USAGE}");
+            return false;
+        }
+    };
+    let all_inputs = opts.has("--all-inputs");
+    // the solutions report their own panics as failures below
+    std::panic::set_hook(Box::new(|_| {}));
+
+    let (mut failed, mut checked, mut unchecked, mut missing) = (0, 0, 0, 0);
+    for solution in selected(&opts.targets) {
+        let id = solution.id;
+        let inputs = match input::all(&input_path(id)) {
+            Ok(inputs) => inputs,
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                println!("{id}: no input");
+                missing += 1;
+                continue;
+            }
+            Err(e) => {
+                println!("{id}: can't list inputs: {e}");
+                failed += 1;
+                continue;
+            }
+        };
+        for named in inputs.iter().take(if all_inputs { usize::MAX } else { 1 }) {
+            let label = format!("{id} [{}]", named.name);
+            let (text, expected) = match (named.read(), named.expected()) {
+                (Ok(text), Ok(expected)) => (text, expected),
+                (Err(e), _) | (_, Err(e)) => {
+                    println!("{label}: FAIL can't read input: {e}");
+                    failed += 1;
+                    continue;
+                }
+            };
+            let expected = expected.into_iter().find(|(part, _)| *part == id.part);
+            let start = Instant::now();
+            let result = solution.solve(&text);
+            let elapsed = start.elapsed();
+            match (result, expected) {
+                (Err(e), _) => {
+                    println!("{label}: FAIL {e}");
+                    failed += 1;
+                }
+                (Ok(answer), Some((_, want))) if answer.to_string() != want => {
+                    println!("{label}: FAIL got {answer}, expected {want} ({elapsed:.2?})");
+                    failed += 1;
+                }
+                (Ok(answer), Some(_)) => {
+                    println!("{label}: ok {answer} ({elapsed:.2?})");
+                    checked += 1;
+                }
+                (Ok(answer), None) => {
+                    println!("{label}: unchecked {answer} ({elapsed:.2?})");
+                    unchecked += 1;
+                }
+            }
+        }
+    }
+    println!(
+        "{checked} ok, {failed} failed, {unchecked} without expected answers, {missing} without input"
+    );
+    failed == 0
+}
+
+/// Lists what each solution assumes about its input, and whether the default input holds up.
+fn assumptions(args: &[String]) {
+    let opts = match Opts::parse(args, &[]) {
+        Ok(opts) => opts,
+        Err(e) => {
+            eprintln!("{e}\n{USAGE}");
+            return;
+        }
+    };
+    std::panic::set_hook(Box::new(|_| {}));
+    for solution in selected(&opts.targets) {
+        let assumptions = solution
+            .assumptions()
+            .map(|a| (None, a))
+            .chain(solution.variant_assumptions().map(|(v, a)| (Some(v), a)))
+            .collect::<Vec<_>>();
+        if assumptions.is_empty() {
+            continue;
+        }
+        println!("{}", solution.id);
+        let input = input::all(&input_path(solution.id))
+            .ok()
+            .and_then(|inputs| inputs.first()?.read().ok());
+        for (variant, assumption) in assumptions {
+            let status = match &input {
+                None => "no input".to_string(),
+                Some(input) => {
+                    let check = AssertUnwindSafe(|| (assumption.check)(input));
+                    match std::panic::catch_unwind(check) {
+                        Ok(Ok(())) => "ok".to_string(),
+                        Ok(Err(reason)) => format!("FAIL: {reason}"),
+                        Err(_) => "FAIL: panicked".to_string(),
+                    }
+                }
+            };
+            match variant {
+                None => println!("  {}: {}", assumption.name, assumption.description),
+                Some(variant) => println!(
+                    "  {} (only {variant}): {}",
+                    assumption.name, assumption.description
+                ),
+            }
+            println!("    default input: {status}");
+        }
+    }
+}
+
+/// The catalog entries matching positional `22`/`23` and `dayNN` filters, all if there are none.
+fn selected(targets: &[String]) -> Vec<Solution> {
+    let (years, days): (Vec<_>, Vec<_>) = targets.iter().partition(|t| !t.starts_with("day"));
+    aoc::catalog()
+        .into_iter()
+        .filter(|s| {
+            let year = format!("{}", s.id.year % 100);
+            let day = format!("day{:02}", s.id.day);
+            (years.is_empty() || years.contains(&&year))
+                && (days.is_empty() || days.contains(&&day))
+        })
+        .collect()
+}
+
+fn input_path(id: SolutionId) -> String {
+    format!("aoc{}/input/day{:02}", id.year % 100, id.day)
+}
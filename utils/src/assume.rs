@@ -0,0 +1,73 @@
+//! Properties of the real inputs that a solution relies on without them being promised by the
+//! puzzle text. A day lists them in a `pub static ASSUMPTIONS`, and whatever runs the day checks
+//! the ones for the part it's about to run, so a different input fails loudly instead of getting
+//! a plausible wrong answer.
+use std::fmt::{self, Display};
+
+#[derive(Debug, Clone, Copy)]
+pub struct Assumption {
+    pub part: u32,
+    pub name: &'static str,
+    pub description: &'static str,
+    /// `Err` says what about the input broke the assumption.
+    pub check: fn(&str) -> Result<(), String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    pub name: &'static str,
+    pub reason: String,
+}
+impl Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "assumption `{}` doesn't hold: {}",
+            self.name, self.reason
+        )
+    }
+}
+impl std::error::Error for Violation {}
+
+/// Checks every assumption made by `part`, in order, stopping at the first that doesn't hold.
+pub fn check(assumptions: &[Assumption], part: u32, input: &str) -> Result<(), Violation> {
+    for assumption in assumptions.iter().filter(|a| a.part == part) {
+        (assumption.check)(input).map_err(|reason| Violation {
+            name: assumption.name,
+            reason,
+        })?;
+    }
+    Ok(())
+}
+
+#[test]
+fn test_check() {
+    static ASSUMPTIONS: &[Assumption] = &[
+        Assumption {
+            part: 1,
+            name: "short",
+            description: "the input is short",
+            check: |input| match input.len() {
+                0..=3 => Ok(()),
+                n => Err(format!("{n} bytes long")),
+            },
+        },
+        Assumption {
+            part: 2,
+            name: "nonempty",
+            description: "there is an input",
+            check: |input| match input {
+                "" => Err("it's empty".to_string()),
+                _ => Ok(()),
+            },
+        },
+    ];
+    assert_eq!(check(ASSUMPTIONS, 1, ""), Ok(()));
+    assert_eq!(check(ASSUMPTIONS, 2, "long enough"), Ok(()));
+    let violation = check(ASSUMPTIONS, 1, "long enough").unwrap_err();
+    assert_eq!(
+        violation.to_string(),
+        "assumption `short` doesn't hold: 11 bytes long"
+    );
+    assert_eq!(check(ASSUMPTIONS, 2, "").unwrap_err().name, "nonempty");
+}
@@ -0,0 +1,148 @@
+//! Finding where a deterministic simulation starts repeating itself. Every function here looks at
+//! the states `init, step(init), step(step(init)), ...` and returns `(mu, lambda)`: the state
+//! after `mu` steps is the first one that comes up again, `lambda` steps later.
+use std::{collections::HashMap, hash::Hash};
+
+/// Brent's algorithm. Only ever keeps two states around, at the cost of stepping up to about
+/// three times as often as [`hashed`], so it's the one to use when states are big and cheap to
+/// step.
+pub fn brent<T: PartialEq + Clone>(init: T, mut step: impl FnMut(&T) -> T) -> (usize, usize) {
+    // find lambda by moving the tortoise to the hare at each power of two
+    let (mut power, mut lambda) = (1, 1);
+    let mut tortoise = init.clone();
+    let mut hare = step(&init);
+    while tortoise != hare {
+        if power == lambda {
+            tortoise = hare.clone();
+            power *= 2;
+            lambda = 0;
+        }
+        hare = step(&hare);
+        lambda += 1;
+    }
+
+    // then mu, by walking two states lambda apart until they meet
+    let mut tortoise = init.clone();
+    let mut hare = init;
+    for _ in 0..lambda {
+        hare = step(&hare);
+    }
+    let mut mu = 0;
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        hare = step(&hare);
+        mu += 1;
+    }
+    (mu, lambda)
+}
+
+/// Floyd's tortoise and hare. Also keeps only two states, but usually steps more often than
+/// [`brent`] does.
+pub fn floyd<T: PartialEq + Clone>(init: T, mut step: impl FnMut(&T) -> T) -> (usize, usize) {
+    let mut tortoise = step(&init);
+    let mut hare = step(&tortoise);
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        hare = step(&hare);
+        hare = step(&hare);
+    }
+
+    let mut mu = 0;
+    let mut tortoise = init;
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        hare = step(&hare);
+        mu += 1;
+    }
+
+    let mut lambda = 1;
+    let mut hare = step(&tortoise);
+    while tortoise != hare {
+        hare = step(&hare);
+        lambda += 1;
+    }
+    (mu, lambda)
+}
+
+/// Remembers every state, so it steps exactly `mu + lambda` times. The one to use when stepping
+/// is expensive.
+pub fn hashed<T: Hash + Eq + Clone>(init: T, mut step: impl FnMut(&T) -> T) -> (usize, usize) {
+    let mut seen = HashMap::new();
+    let mut state = init;
+    for ix in 0.. {
+        if let Some(mu) = seen.get(&state) {
+            return (*mu, ix - mu);
+        }
+        let next = step(&state);
+        seen.insert(state, ix);
+        state = next;
+    }
+    unreachable!()
+}
+
+/// The state after `n` steps, skipping however many whole loops it can once it finds one. Steps
+/// at most `mu + lambda` times, or `n` if that's fewer.
+pub fn state_after<T: Hash + Eq + Clone>(n: usize, init: T, mut step: impl FnMut(&T) -> T) -> T {
+    let mut seen = HashMap::new();
+    let mut states = vec![];
+    let mut state = init;
+    for ix in 0..n {
+        if let Some(mu) = seen.insert(state.clone(), ix) {
+            let lambda = ix - mu;
+            return states.swap_remove(mu + (n - mu) % lambda);
+        }
+        let next = step(&state);
+        states.push(state);
+        state = next;
+    }
+    state
+}
+
+#[test]
+fn test_cycle() {
+    // a tail of 3 into a loop of 5
+    let step = |x: &u32| if *x < 7 { x + 1 } else { 3 };
+    assert_eq!(brent(0, step), (3, 5));
+    assert_eq!(floyd(0, step), (3, 5));
+    assert_eq!(hashed(0, step), (3, 5));
+    assert_eq!(brent(3, step), (0, 5));
+    assert_eq!(floyd(5, step), (0, 5));
+    assert_eq!(hashed(7, step), (0, 5));
+
+    // a fixed point
+    assert_eq!(brent(4, |_: &u32| 4), (0, 1));
+    assert_eq!(floyd(4, |_: &u32| 4), (0, 1));
+    assert_eq!(hashed(1, |_: &u32| 4), (1, 1));
+
+    // squaring mod a prime, which has all sorts of tails and loops
+    for init in 2..200u64 {
+        let step = |x: &u64| x * x % 1009;
+        let expected = hashed(init, step);
+        assert_eq!(brent(init, step), expected, "{init}");
+        assert_eq!(floyd(init, step), expected, "{init}");
+    }
+}
+
+#[test]
+fn test_state_after() {
+    let step = |x: &u64| (x * x + 1) % 1000;
+    for n in 0..100 {
+        let mut expected = 0;
+        for _ in 0..n {
+            expected = step(&expected);
+        }
+        assert_eq!(state_after(n, 0, step), expected, "{n}");
+    }
+    let (mu, lambda) = hashed(0, step);
+    let n = 1_000_000_000;
+    assert_eq!(
+        state_after(n, 0, step),
+        state_after(mu + (n - mu) % lambda, 0, step)
+    );
+    let mut steps = 0;
+    state_after(n, 0, |x| {
+        steps += 1;
+        step(x)
+    });
+    assert_eq!(steps, mu + lambda);
+}
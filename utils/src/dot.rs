@@ -0,0 +1,112 @@
+//! Minimal writer for Graphviz DOT files, for eyeballing the graph-shaped puzzles.
+use std::fmt::{self, Display, Write};
+
+pub type Attrs<'a> = &'a [(&'a str, &'a str)];
+
+/// Attributes that make a node or edge stand out from the rest of the graph.
+pub const HIGHLIGHT: Attrs<'static> = &[("color", "red"), ("penwidth", "3")];
+
+#[derive(Debug, Clone)]
+pub struct Dot {
+    directed: bool,
+    name: String,
+    lines: Vec<String>,
+}
+
+impl Dot {
+    pub fn graph(name: &str) -> Self {
+        Dot {
+            directed: false,
+            name: name.to_string(),
+            lines: vec![],
+        }
+    }
+    pub fn digraph(name: &str) -> Self {
+        Dot {
+            directed: true,
+            name: name.to_string(),
+            lines: vec![],
+        }
+    }
+    pub fn node(&mut self, id: &str, attrs: Attrs) -> &mut Self {
+        let line = format!("{}{}", quote(id), attr_list(attrs));
+        self.lines.push(line);
+        self
+    }
+    pub fn edge(&mut self, from: &str, to: &str, attrs: Attrs) -> &mut Self {
+        let op = if self.directed { "->" } else { "--" };
+        let line = format!("{} {op} {}{}", quote(from), quote(to), attr_list(attrs));
+        self.lines.push(line);
+        self
+    }
+}
+
+impl Display for Dot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = if self.directed { "digraph" } else { "graph" };
+        writeln!(f, "{kind} {} {{", quote(&self.name))?;
+        for line in &self.lines {
+            writeln!(f, "  {line};")?;
+        }
+        writeln!(f, "}}")
+    }
+}
+
+fn quote(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' | '\\' => {
+                out.push('\\');
+                out.push(c);
+            }
+            '\n' => out.push_str("\\n"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn attr_list(attrs: Attrs) -> String {
+    if attrs.is_empty() {
+        return String::new();
+    }
+    let mut out = String::from(" [");
+    for (ix, (k, v)) in attrs.iter().enumerate() {
+        if ix > 0 {
+            out.push_str(", ");
+        }
+        write!(out, "{k}={}", quote(v)).unwrap();
+    }
+    out.push(']');
+    out
+}
+
+#[test]
+fn test_dot_graph() {
+    let mut dot = Dot::graph("g");
+    dot.node("a", &[("shape", "box")])
+        .edge("a", "b", &[("label", "3")])
+        .edge("b", "c", HIGHLIGHT);
+    assert_eq!(
+        dot.to_string(),
+        r#"graph "g" {
+  "a" [shape="box"];
+  "a" -- "b" [label="3"];
+  "b" -- "c" [color="red", penwidth="3"];
+}
+"#
+    );
+}
+
+#[test]
+fn test_dot_digraph_quoting() {
+    let mut dot = Dot::digraph("d");
+    dot.edge("x\"y", "z", &[("label", "a<2006")]);
+    assert_eq!(
+        dot.to_string(),
+        "digraph \"d\" {\n  \"x\\\"y\" -> \"z\" [label=\"a<2006\"];\n}\n"
+    );
+}
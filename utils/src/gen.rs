@@ -0,0 +1,192 @@
+//! Building blocks for the random puzzle input generators.
+use std::collections::{HashMap, HashSet};
+
+use rand::{rngs::StdRng, Rng};
+
+use crate::{Dir, Grid, Pos, SGrid};
+
+pub const LOWERCASE: &[u8] = b"abcdefghijklmnopqrstuvwxyz";
+pub const UPPERCASE: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+pub fn word(rng: &mut StdRng, len: usize, alphabet: &[u8]) -> String {
+    (0..len)
+        .map(|_| alphabet[rng.gen_range(0..alphabet.len())] as char)
+        .collect()
+}
+
+/// `n` different words, none of which are in `exclude`. Panics if the alphabet is too small to
+/// come up with that many.
+pub fn distinct_words(
+    rng: &mut StdRng,
+    n: usize,
+    len: usize,
+    alphabet: &[u8],
+    exclude: &[&str],
+) -> Vec<String> {
+    assert!(
+        (n + exclude.len()) as f64 <= (alphabet.len() as f64).powi(len as i32) / 2.0,
+        "not enough words of length {len} for {n} distinct ones"
+    );
+    let mut seen: HashSet<String> = exclude.iter().map(|v| v.to_string()).collect();
+    let mut result = vec![];
+    while result.len() < n {
+        let w = word(rng, len, alphabet);
+        if seen.insert(w.clone()) {
+            result.push(w);
+        }
+    }
+    result
+}
+
+/// A `h` by `w` grid with each tile picked from `weights` in proportion to its weight.
+pub fn grid(rng: &mut StdRng, h: usize, w: usize, weights: &[(char, u32)]) -> Grid<char> {
+    let total: u32 = weights.iter().map(|v| v.1).sum();
+    (0..h)
+        .map(|_| {
+            (0..w)
+                .map(|_| {
+                    let mut n = rng.gen_range(0..total);
+                    for (c, weight) in weights {
+                        if n < *weight {
+                            return *c;
+                        }
+                        n -= weight;
+                    }
+                    unreachable!()
+                })
+                .collect()
+        })
+        .collect()
+}
+
+pub fn grid_to_string(grid: &SGrid<char>) -> String {
+    grid.iter()
+        .map(|row| row.iter().collect::<String>())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// A random simple closed loop through the points of a `h` by `w` lattice, as every point along
+/// it in clockwise order (with rows growing downwards).
+///
+/// The loop is the outline of a random column-convex polyomino: every column of squares is one
+/// interval, and neighboring intervals overlap. That rules out both holes and shapes that only
+/// touch at a corner, so the outline never visits a point twice.
+pub fn random_loop(rng: &mut StdRng, h: usize, w: usize) -> Vec<Pos> {
+    assert!(h >= 2 && w >= 2, "need at least a 2x2 lattice for a loop");
+    let (sh, sw) = (h - 1, w - 1);
+    let c0 = rng.gen_range(0..=sw / 4);
+    let c1 = rng.gen_range((sw - 1 - sw / 4).max(c0)..sw);
+    let mut top = rng.gen_range(0..=sh / 3);
+    let mut bot = rng.gen_range((sh - 1 - sh / 3).max(top)..sh);
+    let mut squares = HashSet::<Pos>::new();
+    for col in c0..=c1 {
+        if col > c0 {
+            loop {
+                let t = (top as i64 + rng.gen_range(-2..=2)).clamp(0, sh as i64 - 1) as usize;
+                let b = (bot as i64 + rng.gen_range(-2..=2)).clamp(0, sh as i64 - 1) as usize;
+                if t <= b && t <= bot && b >= top {
+                    (top, bot) = (t, b);
+                    break;
+                }
+            }
+        }
+        for row in top..=bot {
+            squares.insert((row, col));
+        }
+    }
+
+    // every side of a square that faces outwards, oriented clockwise around that square
+    let mut next = HashMap::<Pos, Pos>::new();
+    for &(r, c) in squares.iter() {
+        let outside = |dr: i64, dc: i64| {
+            let (nr, nc) = (r as i64 + dr, c as i64 + dc);
+            nr < 0 || nc < 0 || !squares.contains(&(nr as usize, nc as usize))
+        };
+        if outside(-1, 0) {
+            next.insert((r, c), (r, c + 1));
+        }
+        if outside(0, 1) {
+            next.insert((r, c + 1), (r + 1, c + 1));
+        }
+        if outside(1, 0) {
+            next.insert((r + 1, c + 1), (r + 1, c));
+        }
+        if outside(0, -1) {
+            next.insert((r + 1, c), (r, c));
+        }
+    }
+
+    let start = *next.keys().min().unwrap();
+    let mut result = vec![start];
+    let mut pos = next[&start];
+    while pos != start {
+        result.push(pos);
+        pos = next[&pos];
+    }
+    debug_assert_eq!(result.len(), next.len());
+    result
+}
+
+/// Collapses a loop of neighboring points (like [`random_loop`]) into straight runs. The first
+/// point should be a corner, or its run gets split in two.
+pub fn loop_segments(points: &[Pos]) -> Vec<(Dir, usize)> {
+    let mut result: Vec<(Dir, usize)> = vec![];
+    for ix in 0..points.len() {
+        let (a, b) = (points[ix], points[(ix + 1) % points.len()]);
+        let dir = if b.0 < a.0 {
+            Dir::Up
+        } else if b.0 > a.0 {
+            Dir::Down
+        } else if b.1 < a.1 {
+            Dir::Left
+        } else {
+            Dir::Right
+        };
+        match result.last_mut() {
+            Some((d, len)) if *d == dir => *len += 1,
+            _ => result.push((dir, 1)),
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+fn test_rng() -> StdRng {
+    rand::SeedableRng::seed_from_u64(7)
+}
+
+#[test]
+fn test_random_loop_is_simple() {
+    let mut rng = test_rng();
+    for size in [2, 3, 5, 10, 40] {
+        let points = random_loop(&mut rng, size, size + 3);
+        assert_eq!(points.iter().collect::<HashSet<_>>().len(), points.len());
+        for (ix, a) in points.iter().enumerate() {
+            let b = points[(ix + 1) % points.len()];
+            assert_eq!(a.0.abs_diff(b.0) + a.1.abs_diff(b.1), 1);
+        }
+    }
+}
+
+#[test]
+fn test_loop_segments() {
+    let points = [(0, 0), (0, 1), (0, 2), (1, 2), (1, 1), (1, 0)];
+    assert_eq!(
+        loop_segments(&points),
+        vec![
+            (Dir::Right, 2),
+            (Dir::Down, 1),
+            (Dir::Left, 2),
+            (Dir::Up, 1)
+        ]
+    );
+}
+
+#[test]
+fn test_distinct_words() {
+    let mut rng = test_rng();
+    let words = distinct_words(&mut rng, 50, 2, LOWERCASE, &["rx"]);
+    assert_eq!(words.iter().collect::<HashSet<_>>().len(), 50);
+    assert!(!words.contains(&"rx".to_string()));
+}
@@ -0,0 +1,143 @@
+//! Puzzle inputs on disk.
+//!
+//! `input/dayNN` is either a single input file, or a directory of named inputs (say, one per
+//! person) where the one called `default` is what the day's `main` and tests use. Any input can
+//! have expected answers next to it in `<input>.answers`, one `part1: 1234` line per part.
+use std::{
+    fs,
+    io::{self, ErrorKind},
+    path::{Path, PathBuf},
+};
+
+pub const DEFAULT: &str = "default";
+const ANSWERS_EXT: &str = "answers";
+
+/// Reads the default input at `path`, panicking with the path if it's not there.
+pub fn read(path: &str) -> String {
+    let file = default_path(Path::new(path));
+    fs::read_to_string(&file).unwrap_or_else(|e| panic!("can't read {}: {e}", file.display()))
+}
+
+fn default_path(path: &Path) -> PathBuf {
+    if path.is_dir() {
+        path.join(DEFAULT)
+    } else {
+        path.to_path_buf()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NamedInput {
+    pub name: String,
+    pub path: PathBuf,
+}
+
+impl NamedInput {
+    pub fn read(&self) -> io::Result<String> {
+        fs::read_to_string(&self.path)
+    }
+
+    /// The expected answers as `(part, answer)`, or nothing if there's no answers file.
+    pub fn expected(&self) -> io::Result<Vec<(u32, String)>> {
+        // appended rather than swapped in for an extension, so `big.v2` doesn't get `big`'s
+        let mut answers = self.path.clone().into_os_string();
+        answers.push(format!(".{ANSWERS_EXT}"));
+        match fs::read_to_string(answers) {
+            Ok(s) => parse_answers(&s).map_err(|e| io::Error::new(ErrorKind::InvalidData, e)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(vec![]),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// Every input at `path`, sorted by name with `default` first. A single file is just `default`.
+/// Fails with [`ErrorKind::NotFound`] if there's nothing at `path`.
+pub fn all(path: &str) -> io::Result<Vec<NamedInput>> {
+    let path = Path::new(path);
+    if !path.is_dir() {
+        if !path.exists() {
+            return Err(io::Error::new(
+                ErrorKind::NotFound,
+                format!("no input at {}", path.display()),
+            ));
+        }
+        return Ok(vec![NamedInput {
+            name: DEFAULT.to_string(),
+            path: path.to_path_buf(),
+        }]);
+    }
+    let mut result = vec![];
+    for entry in fs::read_dir(path)? {
+        let path = entry?.path();
+        let is_answers = path.extension().is_some_and(|ext| ext == ANSWERS_EXT);
+        if !path.is_file() || is_answers {
+            continue;
+        }
+        let name = path.file_name().unwrap().to_string_lossy().into_owned();
+        result.push(NamedInput { name, path });
+    }
+    result.sort_by_key(|v| (v.name != DEFAULT, v.name.clone()));
+    Ok(result)
+}
+
+pub fn parse_answers(s: &str) -> Result<Vec<(u32, String)>, String> {
+    s.lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .map(|l| {
+            let (part, answer) = l
+                .split_once(':')
+                .ok_or_else(|| format!("expected `partN: answer`, got: {l}"))?;
+            let part = part
+                .trim()
+                .strip_prefix("part")
+                .and_then(|v| v.parse().ok())
+                .ok_or_else(|| format!("bad part in: {l}"))?;
+            Ok((part, answer.trim().to_string()))
+        })
+        .collect()
+}
+
+#[test]
+fn test_parse_answers() {
+    assert_eq!(
+        parse_answers("# someone's input\npart1: 42\n\npart2:  abc \n"),
+        Ok(vec![(1, "42".to_string()), (2, "abc".to_string())])
+    );
+    assert!(parse_answers("42").is_err());
+    assert!(parse_answers("p1: 42").is_err());
+}
+
+#[test]
+fn test_input_dir() {
+    let dir = std::env::temp_dir().join(format!("utils-input-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    for (name, contents) in [
+        ("zed", "z"),
+        ("default", "d"),
+        ("alice", "a"),
+        ("alice.answers", "part1: 1"),
+        ("big", "b"),
+        ("big.v2", "b2"),
+        ("big.v2.answers", "part2: 2"),
+    ] {
+        fs::write(dir.join(name), contents).unwrap();
+    }
+    let dir_str = dir.to_str().unwrap();
+
+    assert_eq!(read(dir_str), "d");
+    let inputs = all(dir_str).unwrap();
+    let names = inputs.iter().map(|v| v.name.as_str()).collect::<Vec<_>>();
+    assert_eq!(names, ["default", "alice", "big", "big.v2", "zed"]);
+    assert_eq!(inputs[1].expected().unwrap(), vec![(1, "1".to_string())]);
+    assert_eq!(inputs[2].expected().unwrap(), vec![]);
+    assert_eq!(inputs[3].expected().unwrap(), vec![(2, "2".to_string())]);
+    assert_eq!(inputs[4].expected().unwrap(), vec![]);
+
+    let single = all(dir.join("zed").to_str().unwrap()).unwrap();
+    assert_eq!(single[0].name, DEFAULT);
+    assert_eq!(single[0].read().unwrap(), "z");
+    let missing = all(dir.join("nobody").to_str().unwrap()).unwrap_err();
+    assert_eq!(missing.kind(), ErrorKind::NotFound);
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -1,3 +1,16 @@
+pub mod assume;
+pub mod cycle;
+pub mod dot;
+pub mod gen;
+pub mod input;
+pub mod maze;
+pub mod mincut;
+pub mod opts;
+pub mod range_set;
+pub mod search;
+pub mod trace;
+pub mod vcd;
+
 use std::{mem::transmute, ops::RangeInclusive};
 
 pub type Grid<T> = Vec<Vec<T>>;
@@ -207,3 +220,33 @@ fn test_separate_thousands() {
     assert_eq!(separate_thousands("1000"), "1_000");
     assert_eq!(separate_thousands("100"), "100");
 }
+
+/// One implementation of a part, by name.
+pub type Variant<'a, T> = (&'a str, fn(&str) -> T);
+
+/// Panics, listing every variant's answer, unless all the variants of a part agree on `input`.
+/// Meant for property tests that check the different implementations of a part against each
+/// other.
+pub fn assert_variants_agree<T: PartialEq + std::fmt::Debug>(input: &str, variants: &[Variant<T>]) {
+    let answers = variants
+        .iter()
+        .map(|(name, f)| (*name, f(input)))
+        .collect::<Vec<_>>();
+    if answers.iter().any(|(_, answer)| *answer != answers[0].1) {
+        panic!("variants disagree on input:\n{input}\nanswers: {answers:?}");
+    }
+}
+#[test]
+fn test_assert_variants_agree() {
+    fn len(s: &str) -> usize {
+        s.len()
+    }
+    fn chars(s: &str) -> usize {
+        s.chars().count()
+    }
+    assert_variants_agree("abc", &[("len", len), ("chars", chars)]);
+    let disagree = std::panic::catch_unwind(|| {
+        assert_variants_agree("åäö", &[("len", len), ("chars", chars)]);
+    });
+    assert!(disagree.is_err());
+}
@@ -0,0 +1,155 @@
+//! Compressing a maze of one tile wide corridors into a graph between the places where there's
+//! actually a choice to make, so searches only have to look at a few dozen nodes instead of
+//! every tile.
+use crate::{Dir, Pos, PosUtils, SGrid};
+
+/// The junctions of a maze and the corridors between them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JunctionGraph {
+    /// Every passable tile that doesn't have exactly two passable neighbours, so the dead ends
+    /// (like a start and an end on the edge of the map) are in here as well as the forks. Sorted.
+    pub nodes: Vec<Pos>,
+    /// `edges[a]` has a `(b, len)` for every corridor that goes `len` steps from `nodes[a]` to
+    /// `nodes[b]` without passing any other junction. Corridors that lead back to where they
+    /// started are left out.
+    pub edges: Vec<Vec<(usize, usize)>>,
+}
+
+impl JunctionGraph {
+    pub fn index(&self, pos: Pos) -> Option<usize> {
+        self.nodes.binary_search(&pos).ok()
+    }
+}
+
+/// For mazes where every tile can be walked both ways.
+pub fn two_way(_: char) -> Option<Dir> {
+    None
+}
+
+/// Slopes drawn as arrows, which can only be walked off of downhill.
+pub fn slope(c: char) -> Option<Dir> {
+    match c {
+        '^' => Some(Dir::Up),
+        '>' => Some(Dir::Right),
+        'v' => Some(Dir::Down),
+        '<' => Some(Dir::Left),
+        _ => None,
+    }
+}
+
+/// Compresses `grid`, where `passable` says which tiles can be walked on at all. A tile that
+/// `one_way` gives a direction for can only be left going that way, which can make corridors
+/// one-way or cut them off entirely.
+pub fn compress(
+    grid: &SGrid<char>,
+    passable: impl Fn(char) -> bool,
+    one_way: impl Fn(char) -> Option<Dir>,
+) -> JunctionGraph {
+    let open = |pos: Pos| passable(grid[pos.0][pos.1]);
+    let exits = |pos: Pos| {
+        [Dir::Up, Dir::Right, Dir::Down, Dir::Left]
+            .into_iter()
+            .filter_map(move |dir| Some((dir, pos.go_in(dir, grid).filter(|n| open(*n))?)))
+    };
+    let can_leave = |pos: Pos, dir| one_way(grid[pos.0][pos.1]).is_none_or(|d| d == dir);
+
+    let mut nodes = vec![];
+    for (row, line) in grid.iter().enumerate() {
+        for col in 0..line.len() {
+            if open((row, col)) && exits((row, col)).count() != 2 {
+                nodes.push((row, col));
+            }
+        }
+    }
+    let mut graph = JunctionGraph {
+        edges: vec![vec![]; nodes.len()],
+        nodes,
+    };
+
+    for from in 0..graph.nodes.len() {
+        let start = graph.nodes[from];
+        for (dir, first) in exits(start) {
+            if !can_leave(start, dir) {
+                continue;
+            }
+            let (mut prev, mut pos, mut len) = (start, first, 1);
+            loop {
+                if let Some(to) = graph.index(pos) {
+                    if to != from {
+                        graph.edges[from].push((to, len));
+                    }
+                    break;
+                }
+                // not a junction, so there's exactly one way on
+                let (dir, next) = exits(pos).find(|(_, n)| *n != prev).unwrap();
+                if !can_leave(pos, dir) {
+                    break;
+                }
+                (prev, pos, len) = (pos, next, len + 1);
+            }
+        }
+    }
+    graph
+}
+
+#[cfg(test)]
+fn parse(maze: &str) -> crate::Grid<char> {
+    maze.lines().map(|l| l.chars().collect()).collect()
+}
+
+#[test]
+fn test_compress() {
+    let grid = parse(
+        "#.#####
+#.#####
+#.....#
+#.###.#
+#.###.#
+#.....#
+###.###
+###.###",
+    );
+    let graph = compress(&grid, |c| c != '#', two_way);
+    assert_eq!(graph.nodes, vec![(0, 1), (2, 1), (5, 3), (7, 3)]);
+    let mut edges = graph.edges.clone();
+    edges.iter_mut().for_each(|e| e.sort());
+    // two ways around the loop between the forks
+    assert_eq!(
+        edges,
+        vec![
+            vec![(1, 2)],
+            vec![(0, 2), (2, 5), (2, 9)],
+            vec![(1, 5), (1, 9), (3, 2)],
+            vec![(2, 2)],
+        ]
+    );
+}
+
+#[test]
+fn test_compress_one_way() {
+    let grid = parse(
+        "#.#####
+#.#####
+#.....#
+#.###v#
+#.###.#
+#.....#
+###^###
+###.###",
+    );
+    let graph = compress(&grid, |c| c != '#', slope);
+    assert_eq!(graph.nodes, vec![(0, 1), (2, 1), (5, 3), (7, 3)]);
+    let mut edges = graph.edges.clone();
+    edges.iter_mut().for_each(|e| e.sort());
+    // the long way around the loop can only be walked downhill, and the end can be left but not
+    // got to
+    assert_eq!(
+        edges,
+        vec![
+            vec![(1, 2)],
+            vec![(0, 2), (2, 5), (2, 9)],
+            vec![(1, 5)],
+            vec![(2, 2)],
+        ]
+    );
+}
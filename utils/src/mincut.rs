@@ -0,0 +1,128 @@
+//! Global minimum cut of an undirected, weighted graph.
+use std::collections::{BinaryHeap, HashMap};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cut {
+    /// Total weight of the edges crossing the cut.
+    pub weight: u64,
+    /// The nodes on one side of the cut, sorted. Everything else is on the other side.
+    pub side: Vec<usize>,
+}
+
+/// Stoer–Wagner on nodes `0..n`. Parallel edges add up, and a disconnected graph has a cut of
+/// weight 0. `None` if there are fewer than two nodes, since then there's nothing to cut.
+///
+/// Each phase grows a set from some node by repeatedly adding the node most tightly connected
+/// to it. The last node added, `t`, is cut off from everything by exactly its connection to the
+/// set, and that's the best cut that separates `t` from the one added before it, `s`. Any other
+/// cut either separates them too or has them on the same side, so merging `s` and `t` and going
+/// again finds it. Deterministic, and O(nm log n) with the heap.
+pub fn stoer_wagner(n: usize, edges: &[(usize, usize, u64)]) -> Option<Cut> {
+    if n < 2 {
+        return None;
+    }
+    let mut adj = vec![HashMap::<usize, u64>::new(); n];
+    for &(a, b, w) in edges {
+        if a != b {
+            *adj[a].entry(b).or_default() += w;
+            *adj[b].entry(a).or_default() += w;
+        }
+    }
+    let mut members = (0..n).map(|v| vec![v]).collect::<Vec<_>>();
+    let mut alive = (0..n).collect::<Vec<_>>();
+    let mut best: Option<Cut> = None;
+
+    let mut in_set = vec![false; n];
+    let mut weight = vec![0u64; n];
+    while alive.len() > 1 {
+        for &v in &alive {
+            in_set[v] = false;
+            weight[v] = 0;
+        }
+        let mut heap = BinaryHeap::<(u64, usize)>::new();
+        let (mut s, mut t) = (alive[0], alive[0]);
+        let mut unvisited = alive.iter().copied();
+        for _ in 0..alive.len() {
+            // the most connected node left, or any node at all once the rest is unreachable
+            let next = std::iter::from_fn(|| heap.pop())
+                .find(|(w, v)| !in_set[*v] && weight[*v] == *w)
+                .map(|(_, v)| v)
+                .or_else(|| unvisited.find(|v| !in_set[*v]))
+                .unwrap();
+            in_set[next] = true;
+            (s, t) = (t, next);
+            for (&v, &w) in &adj[next] {
+                if !in_set[v] {
+                    weight[v] += w;
+                    heap.push((weight[v], v));
+                }
+            }
+        }
+        if best.as_ref().is_none_or(|b| weight[t] < b.weight) {
+            let mut side = members[t].clone();
+            side.sort();
+            best = Some(Cut {
+                weight: weight[t],
+                side,
+            });
+        }
+
+        // merge t into s
+        let t_adj = std::mem::take(&mut adj[t]);
+        for (v, w) in t_adj {
+            adj[v].remove(&t);
+            if v != s {
+                *adj[s].entry(v).or_default() += w;
+                *adj[v].entry(s).or_default() += w;
+            }
+        }
+        let t_members = std::mem::take(&mut members[t]);
+        members[s].extend(t_members);
+        alive.retain(|v| *v != t);
+    }
+    best
+}
+
+#[test]
+fn test_stoer_wagner() {
+    // two triangles joined by a single edge
+    let edges = [
+        (0, 1, 1),
+        (1, 2, 1),
+        (2, 0, 1),
+        (3, 4, 1),
+        (4, 5, 1),
+        (5, 3, 1),
+        (2, 3, 1),
+    ];
+    let cut = stoer_wagner(6, &edges).unwrap();
+    assert_eq!(cut.weight, 1);
+    assert!(cut.side == [0, 1, 2] || cut.side == [3, 4, 5]);
+
+    // the example from the Stoer–Wagner paper, with a minimum cut of 4
+    let edges = [
+        (0, 1, 2),
+        (0, 4, 3),
+        (1, 2, 3),
+        (1, 4, 2),
+        (1, 5, 2),
+        (2, 3, 4),
+        (2, 6, 2),
+        (3, 6, 2),
+        (3, 7, 2),
+        (4, 5, 3),
+        (5, 6, 1),
+        (6, 7, 3),
+    ];
+    let cut = stoer_wagner(8, &edges).unwrap();
+    assert_eq!(cut.weight, 4);
+    assert!(cut.side == [2, 3, 6, 7] || cut.side == [0, 1, 4, 5]);
+
+    // parallel edges add up, so cutting the single edge is cheaper
+    let cut = stoer_wagner(3, &[(0, 1, 1), (0, 1, 1), (1, 2, 1)]).unwrap();
+    assert_eq!(cut.weight, 1);
+
+    let cut = stoer_wagner(4, &[(0, 1, 5), (2, 3, 5)]).unwrap();
+    assert_eq!(cut.weight, 0);
+    assert_eq!(stoer_wagner(1, &[]), None);
+}
@@ -0,0 +1,59 @@
+//! Command line handling shared by the per-year runners.
+
+/// Positional targets (`all`, `day05`, ...) plus `--flag` options. Flags listed as valued take
+/// the next argument (or `--flag=value`) as their value, every other flag is a plain switch.
+#[derive(Debug, Default, Clone)]
+pub struct Opts {
+    pub targets: Vec<String>,
+    flags: Vec<(String, Option<String>)>,
+}
+
+impl Opts {
+    pub fn parse(args: &[String], valued: &[&str]) -> Result<Self, String> {
+        let mut opts = Opts::default();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            if !arg.starts_with("--") {
+                opts.targets.push(arg.clone());
+                continue;
+            }
+            if let Some((flag, value)) = arg.split_once('=') {
+                opts.flags.push((flag.to_string(), Some(value.to_string())));
+            } else if valued.contains(&arg.as_str()) {
+                let Some(value) = args.next() else {
+                    return Err(format!("missing value for {arg}"));
+                };
+                opts.flags.push((arg.clone(), Some(value.clone())));
+            } else {
+                opts.flags.push((arg.clone(), None));
+            }
+        }
+        Ok(opts)
+    }
+
+    pub fn has(&self, flag: &str) -> bool {
+        self.flags.iter().any(|(f, _)| f == flag)
+    }
+
+    pub fn get(&self, flag: &str) -> Option<&str> {
+        self.flags
+            .iter()
+            .rev()
+            .find(|(f, _)| f == flag)
+            .and_then(|(_, v)| v.as_deref())
+    }
+}
+
+#[test]
+fn test_opts_parse() {
+    let args = ["day25", "--dot", "out.dot", "--bench", "day20", "--x=1"].map(String::from);
+    let opts = Opts::parse(&args, &["--dot"]).unwrap();
+    assert_eq!(opts.targets, vec!["day25", "day20"]);
+    assert_eq!(opts.get("--dot"), Some("out.dot"));
+    assert_eq!(opts.get("--x"), Some("1"));
+    assert!(opts.has("--bench"));
+    assert_eq!(opts.get("--bench"), None);
+    assert!(!opts.has("--nope"));
+
+    assert!(Opts::parse(&["--dot".to_string()], &["--dot"]).is_err());
+}
@@ -0,0 +1,248 @@
+//! Sets of integers kept as sorted ranges, for puzzles that push whole ranges of values through
+//! a series of cuts instead of one value at a time.
+use std::{fmt::Debug, ops::RangeInclusive};
+
+/// The integer types a [`RangeSet`] can hold. Only up to 64 bits, so that [`RangeSet::len`]
+/// always fits in a `u128`.
+pub trait Endpoint: Copy + Ord + Debug {
+    const MIN: Self;
+    const MAX: Self;
+    /// The next value up, or `None` at `MAX`.
+    fn succ(self) -> Option<Self>;
+    /// The next value down, or `None` at `MIN`.
+    fn pred(self) -> Option<Self>;
+    /// How many values there are from `start` to `end`, counting both.
+    fn count(start: Self, end: Self) -> u128;
+}
+
+macro_rules! endpoint {
+    ($($t:ty),*) => {$(
+        impl Endpoint for $t {
+            const MIN: Self = <$t>::MIN;
+            const MAX: Self = <$t>::MAX;
+            fn succ(self) -> Option<Self> {
+                self.checked_add(1)
+            }
+            fn pred(self) -> Option<Self> {
+                self.checked_sub(1)
+            }
+            fn count(start: Self, end: Self) -> u128 {
+                end.abs_diff(start) as u128 + 1
+            }
+        }
+    )*};
+}
+endpoint!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+/// A set of integers, stored as the fewest ranges that cover it exactly. They're sorted, and no
+/// two of them overlap or even touch.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RangeSet<T> {
+    ranges: Vec<(T, T)>,
+}
+
+impl<T: Endpoint> Default for RangeSet<T> {
+    fn default() -> Self {
+        RangeSet::new()
+    }
+}
+
+impl<T: Endpoint> RangeSet<T> {
+    pub fn new() -> Self {
+        RangeSet { ranges: vec![] }
+    }
+
+    /// Everything from `T::MIN` to `T::MAX`.
+    pub fn full() -> Self {
+        RangeSet::from(T::MIN..=T::MAX)
+    }
+
+    /// Sorts and merges any old ranges, dropping empty ones.
+    fn normalize(mut ranges: Vec<(T, T)>) -> Self {
+        ranges.retain(|(start, end)| start <= end);
+        ranges.sort_unstable();
+        let mut merged: Vec<(T, T)> = Vec::with_capacity(ranges.len());
+        for (start, end) in ranges {
+            match merged.last_mut() {
+                Some(last) if last.1.succ().is_none_or(|next| start <= next) => {
+                    last.1 = last.1.max(end);
+                }
+                _ => merged.push((start, end)),
+            }
+        }
+        RangeSet { ranges: merged }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// How many values are in the set.
+    pub fn len(&self) -> u128 {
+        self.ranges
+            .iter()
+            .map(|(start, end)| T::count(*start, *end))
+            .sum()
+    }
+
+    pub fn min(&self) -> Option<T> {
+        self.ranges.first().map(|r| r.0)
+    }
+
+    pub fn max(&self) -> Option<T> {
+        self.ranges.last().map(|r| r.1)
+    }
+
+    pub fn contains(&self, value: T) -> bool {
+        let ix = self.ranges.partition_point(|(_, end)| *end < value);
+        self.ranges
+            .get(ix)
+            .is_some_and(|(start, _)| *start <= value)
+    }
+
+    /// The ranges in the set, lowest first.
+    pub fn iter(&self) -> impl Iterator<Item = RangeInclusive<T>> + '_ {
+        self.ranges.iter().map(|(start, end)| *start..=*end)
+    }
+
+    pub fn insert(&mut self, range: RangeInclusive<T>) {
+        *self = self.union(&RangeSet::from(range));
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        RangeSet::normalize(self.ranges.iter().chain(&other.ranges).copied().collect())
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        let mut out = vec![];
+        let (mut a, mut b) = (
+            self.ranges.iter().peekable(),
+            other.ranges.iter().peekable(),
+        );
+        while let (Some(x), Some(y)) = (a.peek(), b.peek()) {
+            let (start, end) = (x.0.max(y.0), x.1.min(y.1));
+            if start <= end {
+                out.push((start, end));
+            }
+            // whichever ends first can't overlap anything else in the other
+            if x.1 < y.1 {
+                a.next();
+            } else {
+                b.next();
+            }
+        }
+        // the pieces are already sorted and apart, since they're cut from disjoint ranges
+        RangeSet { ranges: out }
+    }
+
+    /// Everything of `T` that isn't in the set.
+    pub fn complement(&self) -> Self {
+        let mut out = vec![];
+        let mut from = Some(T::MIN);
+        for (start, end) in &self.ranges {
+            if let Some(gap_start) = from.filter(|gap_start| gap_start < start) {
+                out.push((gap_start, start.pred().unwrap()));
+            }
+            from = end.succ();
+        }
+        if let Some(gap_start) = from {
+            out.push((gap_start, T::MAX));
+        }
+        RangeSet { ranges: out }
+    }
+
+    pub fn difference(&self, other: &Self) -> Self {
+        self.intersection(&other.complement())
+    }
+
+    /// The values below `at`, and the ones from `at` up.
+    pub fn split_at(&self, at: T) -> (Self, Self) {
+        let below = match at.pred() {
+            Some(pred) => self.intersection(&RangeSet::from(T::MIN..=pred)),
+            None => RangeSet::new(),
+        };
+        (below, self.intersection(&RangeSet::from(at..=T::MAX)))
+    }
+}
+
+impl<T: Endpoint> From<RangeInclusive<T>> for RangeSet<T> {
+    fn from(range: RangeInclusive<T>) -> Self {
+        RangeSet::normalize(vec![range.into_inner()])
+    }
+}
+
+impl<T: Endpoint> FromIterator<RangeInclusive<T>> for RangeSet<T> {
+    fn from_iter<I: IntoIterator<Item = RangeInclusive<T>>>(iter: I) -> Self {
+        RangeSet::normalize(iter.into_iter().map(RangeInclusive::into_inner).collect())
+    }
+}
+
+#[test]
+fn test_range_set() {
+    // empty ranges are dropped
+    let empty = RangeInclusive::new(20, 19);
+    let set = RangeSet::from_iter([5..=10, 1..=2, 3..=3, empty, 8..=12]);
+    assert_eq!(set.iter().collect::<Vec<_>>(), vec![1..=3, 5..=12]);
+    assert_eq!(set.len(), 11);
+    assert!(set.contains(3) && !set.contains(4) && set.contains(12) && !set.contains(13));
+    let (below, above) = set.split_at(6);
+    assert_eq!(below.iter().collect::<Vec<_>>(), vec![1..=3, 5..=5]);
+    assert_eq!(above.iter().collect::<Vec<_>>(), vec![6..=12]);
+    assert_eq!(
+        set.difference(&RangeSet::from(2..=6))
+            .iter()
+            .collect::<Vec<_>>(),
+        vec![1..=1, 7..=12]
+    );
+
+    // the edges of the type don't overflow
+    let full = RangeSet::<u64>::full();
+    assert_eq!(full.len(), 1 << 64);
+    assert!(full.complement().is_empty());
+    assert_eq!(full.split_at(0).0, RangeSet::new());
+    assert_eq!(RangeSet::<i8>::new().complement(), RangeSet::full());
+}
+
+#[cfg(test)]
+fn arb_set() -> impl proptest::strategy::Strategy<Value = RangeSet<i8>> {
+    use proptest::prelude::*;
+    prop::collection::vec((any::<i8>(), 0u8..40), 0..6).prop_map(|ranges| {
+        ranges
+            .into_iter()
+            .map(|(start, len)| start..=start.saturating_add_unsigned(len))
+            .collect()
+    })
+}
+
+#[cfg(test)]
+fn members(set: &RangeSet<i8>) -> std::collections::BTreeSet<i8> {
+    set.iter().flatten().collect()
+}
+
+#[cfg(test)]
+proptest::proptest! {
+    #[test]
+    fn test_range_set_ops(a in arb_set(), b in arb_set(), at: i8) {
+        let (ma, mb) = (members(&a), members(&b));
+        for set in [&a, &b] {
+            // sorted, and with a gap between each pair of ranges
+            for (x, y) in set.ranges.iter().zip(set.ranges.iter().skip(1)) {
+                proptest::prop_assert!(x.0 <= x.1 && x.1.succ().unwrap() < y.0);
+            }
+        }
+        proptest::prop_assert_eq!(a.len(), ma.len() as u128);
+        proptest::prop_assert_eq!(members(&a.union(&b)), &ma | &mb);
+        proptest::prop_assert_eq!(members(&a.intersection(&b)), &ma & &mb);
+        proptest::prop_assert_eq!(members(&a.difference(&b)), &ma - &mb);
+        proptest::prop_assert_eq!(
+            members(&a.complement()),
+            (i8::MIN..=i8::MAX).filter(|v| !ma.contains(v)).collect()
+        );
+        let (below, above) = a.split_at(at);
+        proptest::prop_assert_eq!(members(&below), ma.range(..at).copied().collect());
+        proptest::prop_assert_eq!(members(&above), ma.range(at..).copied().collect());
+        proptest::prop_assert_eq!(a.contains(at), ma.contains(&at));
+        proptest::prop_assert_eq!(a.min(), ma.first().copied());
+        proptest::prop_assert_eq!(a.max(), ma.last().copied());
+    }
+}
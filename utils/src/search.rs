@@ -0,0 +1,376 @@
+//! Shortest paths over graphs that are only known through a successor function, so the states
+//! can be anything hashable: a position, a position and a heading, a bitmask of visited
+//! valves...
+//!
+//! Start with [`Search::from`], say what to keep track of, then run one of the algorithms:
+//!
+//! ```
+//! use utils::search::Search;
+//! // steps of +1 or *2 from 1 to 10
+//! let found = Search::from([1u32])
+//!     .keep_paths()
+//!     .bfs(|n| [n + 1, n * 2].into_iter().filter(|n| *n <= 10), |n| *n == 10);
+//! assert_eq!(found.cost(), Some(4));
+//! assert_eq!(found.path(), Some(vec![1, 2, 4, 5, 10]));
+//! ```
+use std::{
+    cmp::Reverse,
+    collections::{hash_map::Entry, BinaryHeap, VecDeque},
+    hash::Hash,
+};
+
+use rustc_hash::FxHashMap;
+
+/// Where to start, and what to remember along the way.
+#[derive(Debug, Clone)]
+pub struct Search<S> {
+    starts: Vec<S>,
+    paths: bool,
+    max_cost: Option<u64>,
+}
+
+/// How much work a search did.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// States taken off the queue and expanded, each once unless an inconsistent heuristic made
+    /// A* come back to it.
+    pub expanded: usize,
+    /// States put on the queue, counting the ones later found to be stale.
+    pub pushed: usize,
+}
+
+/// What a search found. Every state it expanded has its best cost, as long as the A* heuristic
+/// is consistent.
+#[derive(Debug, Clone)]
+pub struct Found<S> {
+    /// The first goal state expanded, if there was one.
+    pub goal: Option<S>,
+    costs: FxHashMap<S, u64>,
+    /// Only kept with [`Search::keep_paths`].
+    parents: Option<FxHashMap<S, S>>,
+    pub stats: Stats,
+}
+
+impl<S: Hash + Eq + Clone> Search<S> {
+    pub fn from(starts: impl IntoIterator<Item = S>) -> Self {
+        Search {
+            starts: starts.into_iter().collect(),
+            paths: false,
+            max_cost: None,
+        }
+    }
+
+    /// Remember where each state was first reached from, so [`Found::path`] can work. Costs a
+    /// clone and a map insert per state.
+    pub fn keep_paths(mut self) -> Self {
+        self.paths = true;
+        self
+    }
+
+    /// Don't go anywhere that costs more than `max_cost` to get to.
+    pub fn max_cost(mut self, max_cost: u64) -> Self {
+        self.max_cost = Some(max_cost);
+        self
+    }
+
+    /// Breadth first, for when every step costs 1.
+    pub fn bfs<I: IntoIterator<Item = S>>(
+        self,
+        mut successors: impl FnMut(&S) -> I,
+        mut is_goal: impl FnMut(&S) -> bool,
+    ) -> Found<S> {
+        let mut found = Found::new(self.paths);
+        let mut q = VecDeque::new();
+        for start in self.starts {
+            if found.reach(start.clone(), 0, None) {
+                q.push_back((start, 0));
+            }
+        }
+        found.stats.pushed = q.len();
+        while let Some((state, cost)) = q.pop_front() {
+            found.stats.expanded += 1;
+            if is_goal(&state) {
+                found.goal = Some(state);
+                break;
+            }
+            if self.max_cost.is_some_and(|max| cost >= max) {
+                continue;
+            }
+            for next in successors(&state) {
+                // the first time a state is reached is the cheapest, so it's settled right away
+                if found.reach(next.clone(), cost + 1, Some(&state)) {
+                    found.stats.pushed += 1;
+                    q.push_back((next, cost + 1));
+                }
+            }
+        }
+        found
+    }
+
+    /// Dijkstra's algorithm with a binary heap, for any non-negative step costs. `successors`
+    /// gets each state along with what it cost to get there, and gives the steps out of it.
+    pub fn dijkstra<I: IntoIterator<Item = (S, u64)>>(
+        self,
+        successors: impl FnMut(&S, u64) -> I,
+        is_goal: impl FnMut(&S) -> bool,
+    ) -> Found<S> {
+        self.astar(successors, |_| 0, is_goal)
+    }
+
+    /// A*, where `heuristic` never overestimates what's left to a goal and doesn't drop by more
+    /// than a step costs. With a heuristic of 0 it's Dijkstra.
+    pub fn astar<I: IntoIterator<Item = (S, u64)>>(
+        self,
+        mut successors: impl FnMut(&S, u64) -> I,
+        mut heuristic: impl FnMut(&S) -> u64,
+        mut is_goal: impl FnMut(&S) -> bool,
+    ) -> Found<S> {
+        let mut found = Found::new(self.paths);
+        // the heap only holds indices into `queued`, so states don't need to be Ord
+        let mut queued = vec![];
+        let mut heap = BinaryHeap::new();
+        for start in self.starts {
+            if found.reach(start.clone(), 0, None) {
+                heap.push(Reverse((heuristic(&start), 0, queued.len())));
+                queued.push(Some(start));
+            }
+        }
+        while let Some(Reverse((_, cost, ix))) = heap.pop() {
+            let state = queued[ix].take().unwrap();
+            // states are only pushed when their cost improves, so anything but the latest push
+            // of a state is stale
+            if found.costs[&state] < cost {
+                continue;
+            }
+            found.stats.expanded += 1;
+            if is_goal(&state) {
+                found.goal = Some(state);
+                break;
+            }
+            for (next, step) in successors(&state, cost) {
+                let cost = cost + step;
+                if self.max_cost.is_some_and(|max| cost > max) {
+                    continue;
+                }
+                if found.reach(next.clone(), cost, Some(&state)) {
+                    heap.push(Reverse((cost + heuristic(&next), cost, queued.len())));
+                    queued.push(Some(next));
+                }
+            }
+        }
+        found.stats.pushed = queued.len();
+        found
+    }
+
+    /// Dijkstra with a bucket per cost instead of a heap, which is quicker when step costs are
+    /// small integers. The buckets go up to the most expensive state reached, so the costs
+    /// themselves should stay small too.
+    pub fn buckets<I: IntoIterator<Item = (S, u64)>>(
+        self,
+        mut successors: impl FnMut(&S, u64) -> I,
+        mut is_goal: impl FnMut(&S) -> bool,
+    ) -> Found<S> {
+        let mut found = Found::new(self.paths);
+        let mut buckets: Vec<Vec<S>> = vec![vec![]];
+        for start in self.starts {
+            if found.reach(start.clone(), 0, None) {
+                found.stats.pushed += 1;
+                buckets[0].push(start);
+            }
+        }
+        let mut cost = 0;
+        while cost < buckets.len() {
+            let Some(state) = buckets[cost].pop() else {
+                cost += 1;
+                continue;
+            };
+            if found.costs[&state] < cost as u64 {
+                continue;
+            }
+            found.stats.expanded += 1;
+            if is_goal(&state) {
+                found.goal = Some(state);
+                break;
+            }
+            for (next, step) in successors(&state, cost as u64) {
+                let next_cost = cost + step as usize;
+                if self.max_cost.is_some_and(|max| next_cost as u64 > max) {
+                    continue;
+                }
+                if found.reach(next.clone(), next_cost as u64, Some(&state)) {
+                    if buckets.len() <= next_cost {
+                        buckets.resize(next_cost + 1, vec![]);
+                    }
+                    found.stats.pushed += 1;
+                    buckets[next_cost].push(next);
+                }
+            }
+        }
+        found
+    }
+}
+
+impl<S: Hash + Eq + Clone> Found<S> {
+    fn new(paths: bool) -> Self {
+        Found {
+            goal: None,
+            costs: FxHashMap::default(),
+            parents: paths.then(FxHashMap::default),
+            stats: Stats::default(),
+        }
+    }
+
+    /// Records getting to `state` for `cost` if that's the best way there so far.
+    fn reach(&mut self, state: S, cost: u64, from: Option<&S>) -> bool {
+        let better = match self.costs.entry(state.clone()) {
+            Entry::Occupied(mut e) if cost < *e.get() => {
+                e.insert(cost);
+                true
+            }
+            Entry::Occupied(_) => false,
+            Entry::Vacant(e) => {
+                e.insert(cost);
+                true
+            }
+        };
+        if let (true, Some(parents), Some(from)) = (better, &mut self.parents, from) {
+            parents.insert(state, from.clone());
+        }
+        better
+    }
+
+    /// What it cost to get to the goal.
+    pub fn cost(&self) -> Option<u64> {
+        self.goal.as_ref().map(|goal| self.costs[goal])
+    }
+
+    /// The best known cost of getting to `state`, if it was reached.
+    pub fn cost_of(&self, state: &S) -> Option<u64> {
+        self.costs.get(state).copied()
+    }
+
+    /// Every state that was reached, with the best known cost of getting to it.
+    pub fn costs(&self) -> impl Iterator<Item = (&S, u64)> {
+        self.costs.iter().map(|(state, cost)| (state, *cost))
+    }
+
+    /// The states from a start to the goal, both included. `None` without a goal, or without
+    /// [`Search::keep_paths`].
+    pub fn path(&self) -> Option<Vec<S>> {
+        self.path_to(self.goal.as_ref()?)
+    }
+
+    /// The states from a start to `state`, both included. `None` if `state` wasn't reached, or
+    /// without [`Search::keep_paths`].
+    pub fn path_to(&self, state: &S) -> Option<Vec<S>> {
+        let parents = self.parents.as_ref()?;
+        self.costs.get(state)?;
+        let mut path = vec![state.clone()];
+        while let Some(parent) = parents.get(path.last().unwrap()) {
+            path.push(parent.clone());
+        }
+        path.reverse();
+        Some(path)
+    }
+}
+
+/// Moving around a grid of digits, paying the digit of each cell moved into.
+#[cfg(test)]
+fn grid_successors<'a>(
+    grid: &'a [&str],
+) -> impl FnMut(&crate::Pos, u64) -> Vec<(crate::Pos, u64)> + 'a {
+    move |&(r, c), _| {
+        let mut next = vec![];
+        if r > 0 {
+            next.push((r - 1, c));
+        }
+        if c > 0 {
+            next.push((r, c - 1));
+        }
+        if r + 1 < grid.len() {
+            next.push((r + 1, c));
+        }
+        if c + 1 < grid[0].len() {
+            next.push((r, c + 1));
+        }
+        next.into_iter()
+            .filter(|(r, c)| grid[*r].as_bytes()[*c] != b'#')
+            .map(|(r, c)| ((r, c), (grid[r].as_bytes()[c] - b'0') as u64))
+            .collect()
+    }
+}
+
+#[test]
+fn test_weighted() {
+    // the only way through without stepping on a 9 is down the staircase of 1s
+    let grid = ["1199", "9119", "9911", "#991"];
+    let goal = (3, 3);
+    let manhattan = |(r, c): &(usize, usize)| (goal.0 - r + goal.1 - c) as u64;
+    let runs = [
+        Search::from([(0, 0)])
+            .keep_paths()
+            .dijkstra(grid_successors(&grid), |p| *p == goal),
+        Search::from([(0, 0)])
+            .keep_paths()
+            .astar(grid_successors(&grid), manhattan, |p| *p == goal),
+        Search::from([(0, 0)])
+            .keep_paths()
+            .buckets(grid_successors(&grid), |p| *p == goal),
+    ];
+    for found in &runs {
+        assert_eq!(found.cost(), Some(6));
+        assert_eq!(
+            found.path().unwrap(),
+            [(0, 0), (0, 1), (1, 1), (1, 2), (2, 2), (2, 3), (3, 3)]
+        );
+        assert!(found.stats.expanded <= found.stats.pushed);
+    }
+    assert!(runs[1].stats.expanded <= runs[0].stats.expanded);
+
+    // no way to the blocked corner, so everything else gets expanded
+    for found in [
+        Search::from([(0, 0)]).dijkstra(grid_successors(&grid), |p| *p == (3, 0)),
+        Search::from([(0, 0)]).buckets(grid_successors(&grid), |p| *p == (3, 0)),
+    ] {
+        assert_eq!(found.goal, None);
+        assert_eq!(found.cost(), None);
+        assert_eq!(found.costs().count(), 15);
+        assert_eq!(found.stats.expanded, 15);
+        assert_eq!(found.cost_of(&(3, 3)), Some(6));
+        assert_eq!(found.path(), None);
+    }
+
+    let found = Search::from([(0, 0)])
+        .max_cost(10)
+        .dijkstra(grid_successors(&grid), |_| false);
+    assert!(found.costs().all(|(_, cost)| cost <= 10));
+    assert_eq!(found.cost_of(&(3, 3)), Some(6));
+    assert_eq!(found.cost_of(&(0, 2)), Some(10));
+    assert_eq!(found.cost_of(&(0, 3)), None);
+}
+
+#[test]
+fn test_bfs() {
+    // a ring of 10, from two starts
+    let found = Search::from([0, 5])
+        .keep_paths()
+        .bfs(|n: &u32| [(n + 1) % 10, (n + 9) % 10], |n| *n == 8);
+    assert_eq!(found.cost(), Some(2));
+    assert_eq!(found.path(), Some(vec![0, 9, 8]));
+    assert_eq!(found.path_to(&6), Some(vec![5, 6]));
+
+    let found = Search::from([0])
+        .max_cost(3)
+        .bfs(|n: &u32| [(n + 1) % 10, (n + 9) % 10], |_| false);
+    let mut costs = found
+        .costs()
+        .map(|(n, cost)| (*n, cost))
+        .collect::<Vec<_>>();
+    costs.sort();
+    assert_eq!(
+        costs,
+        [(0, 0), (1, 1), (2, 2), (3, 3), (7, 3), (8, 2), (9, 1)]
+    );
+    assert_eq!(found.stats.expanded, 7);
+    assert_eq!(found.path_to(&3), None);
+}
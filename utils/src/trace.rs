@@ -0,0 +1,247 @@
+//! Structured events emitted by solutions, switched on from the command line with `--trace`.
+//!
+//! Solutions call [`event!`](crate::event) and pay a single atomic load when tracing is off.
+use std::{
+    fmt::{self, Display, Write as _},
+    fs::File,
+    io::{BufWriter, Write},
+    sync::{
+        atomic::{AtomicU8, Ordering},
+        Mutex,
+    },
+};
+
+use crate::Dir;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[repr(u8)]
+pub enum Level {
+    Info = 1,
+    Debug = 2,
+    Trace = 3,
+}
+impl Level {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "info" => Some(Level::Info),
+            "debug" => Some(Level::Debug),
+            "trace" => Some(Level::Trace),
+            _ => None,
+        }
+    }
+    pub fn name(self) -> &'static str {
+        match self {
+            Level::Info => "info",
+            Level::Debug => "debug",
+            Level::Trace => "trace",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Int(i64),
+    Bool(bool),
+    Str(String),
+}
+impl Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Int(v) => write!(f, "{v}"),
+            Value::Bool(v) => write!(f, "{v}"),
+            Value::Str(v) => write!(f, "{v}"),
+        }
+    }
+}
+macro_rules! value_from_int {
+    ($($t:ty),*) => {
+        $(impl From<$t> for Value {
+            fn from(v: $t) -> Self {
+                Value::Int(v as i64)
+            }
+        })*
+    };
+}
+value_from_int!(i32, i64, u8, u32, u64, usize);
+impl From<bool> for Value {
+    fn from(v: bool) -> Self {
+        Value::Bool(v)
+    }
+}
+impl From<&str> for Value {
+    fn from(v: &str) -> Self {
+        Value::Str(v.to_string())
+    }
+}
+impl From<String> for Value {
+    fn from(v: String) -> Self {
+        Value::Str(v)
+    }
+}
+impl From<char> for Value {
+    fn from(v: char) -> Self {
+        Value::Str(v.to_string())
+    }
+}
+impl From<Dir> for Value {
+    fn from(v: Dir) -> Self {
+        Value::Str(format!("{v:?}"))
+    }
+}
+
+enum Sink {
+    Stderr,
+    Json(BufWriter<File>),
+}
+struct State {
+    sink: Sink,
+    scope: String,
+}
+
+static LEVEL: AtomicU8 = AtomicU8::new(0);
+static STATE: Mutex<Option<State>> = Mutex::new(None);
+
+/// Starts emitting events up to `level`, as text on stderr or as JSON lines written to `path`.
+pub fn enable(level: Level, path: Option<&str>) -> std::io::Result<()> {
+    let sink = match path {
+        Some(path) => Sink::Json(BufWriter::new(File::create(path)?)),
+        None => Sink::Stderr,
+    };
+    *STATE.lock().unwrap() = Some(State {
+        sink,
+        scope: String::new(),
+    });
+    LEVEL.store(level as u8, Ordering::Relaxed);
+    Ok(())
+}
+
+/// Stops tracing and flushes whatever was buffered.
+pub fn disable() {
+    LEVEL.store(0, Ordering::Relaxed);
+    if let Some(State {
+        sink: Sink::Json(mut w),
+        ..
+    }) = STATE.lock().unwrap().take()
+    {
+        w.flush().unwrap();
+    }
+}
+
+/// Tags the following events with the day (or whatever else) currently running.
+pub fn set_scope(scope: &str) {
+    if let Some(state) = STATE.lock().unwrap().as_mut() {
+        state.scope = scope.to_string();
+    }
+}
+
+pub fn enabled(level: Level) -> bool {
+    level as u8 <= LEVEL.load(Ordering::Relaxed)
+}
+
+pub fn emit(level: Level, kind: &str, fields: &[(&str, Value)]) {
+    let mut state = STATE.lock().unwrap();
+    let Some(state) = state.as_mut() else {
+        return;
+    };
+    match &mut state.sink {
+        Sink::Stderr => eprintln!("{}", text_line(&state.scope, level, kind, fields)),
+        Sink::Json(w) => writeln!(w, "{}", json_line(&state.scope, level, kind, fields)).unwrap(),
+    }
+}
+
+/// Runs `emit` out of line, so the fields [`event!`](crate::event) builds go on this function's
+/// stack frame rather than the caller's, which matters for callers that recurse deeply.
+#[doc(hidden)]
+#[cold]
+#[inline(never)]
+pub fn emit_with(emit: impl FnOnce()) {
+    emit()
+}
+
+/// Emits an event if tracing is enabled for its level, e.g.
+/// `event!(Level::Debug, "pulse", from = sender, to = id, high = sig)`.
+#[macro_export]
+macro_rules! event {
+    ($level:expr, $kind:expr $(, $key:ident = $value:expr)* $(,)?) => {
+        if $crate::trace::enabled($level) {
+            $crate::trace::emit_with(|| {
+                $crate::trace::emit(
+                    $level,
+                    $kind,
+                    &[$((stringify!($key), $crate::trace::Value::from($value))),*],
+                )
+            });
+        }
+    };
+}
+
+fn text_line(scope: &str, level: Level, kind: &str, fields: &[(&str, Value)]) -> String {
+    let mut out = format!("{scope} {} {kind}", level.name());
+    for (k, v) in fields {
+        write!(out, " {k}={v}").unwrap();
+    }
+    out
+}
+
+fn json_line(scope: &str, level: Level, kind: &str, fields: &[(&str, Value)]) -> String {
+    let mut out = String::from("{");
+    let head = [
+        ("day", Value::from(scope)),
+        ("level", Value::from(level.name())),
+        ("event", Value::from(kind)),
+    ];
+    for (ix, (k, v)) in head.iter().chain(fields).enumerate() {
+        if ix > 0 {
+            out.push(',');
+        }
+        json_str(&mut out, k);
+        out.push(':');
+        match v {
+            Value::Str(s) => json_str(&mut out, s),
+            v => write!(out, "{v}").unwrap(),
+        }
+    }
+    out.push('}');
+    out
+}
+
+fn json_str(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+#[test]
+fn test_trace_lines() {
+    let fields = [
+        ("from", Value::from("a\"b")),
+        ("to", Value::from("inv")),
+        ("high", Value::from(true)),
+        ("n", Value::from(3usize)),
+    ];
+    assert_eq!(
+        text_line("day20", Level::Debug, "pulse", &fields),
+        "day20 debug pulse from=a\"b to=inv high=true n=3"
+    );
+    assert_eq!(
+        json_line("day20", Level::Debug, "pulse", &fields),
+        r#"{"day":"day20","level":"debug","event":"pulse","from":"a\"b","to":"inv","high":true,"n":3}"#
+    );
+}
+
+#[test]
+fn test_trace_level() {
+    assert_eq!(Level::parse("debug"), Some(Level::Debug));
+    assert_eq!(Level::parse("loud"), None);
+    assert!(Level::Info < Level::Trace);
+    // nothing enables tracing in tests
+    assert!(!enabled(Level::Info));
+}
@@ -0,0 +1,152 @@
+//! Minimal writer for Value Change Dump files, for watching a simulation's signals over time in
+//! a waveform viewer like GTKWave.
+use std::fmt::{self, Display, Write};
+
+/// A wire declared with [`Vcd::wire`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Signal(usize);
+
+#[derive(Debug, Clone)]
+pub struct Vcd {
+    timescale: String,
+    definitions: Vec<String>,
+    widths: Vec<usize>,
+    values: Vec<Option<u64>>,
+    time: Option<u64>,
+    changes: String,
+}
+
+impl Vcd {
+    /// `timescale` is how long one tick is, like `1ns`.
+    pub fn new(timescale: &str) -> Self {
+        Vcd {
+            timescale: timescale.to_string(),
+            definitions: vec![],
+            widths: vec![],
+            values: vec![],
+            time: None,
+            changes: String::new(),
+        }
+    }
+    /// Opens a scope that the wires declared until the matching [`Vcd::upscope`] go in.
+    pub fn scope(&mut self, name: &str) -> &mut Self {
+        self.definitions.push(format!("$scope module {name} $end"));
+        self
+    }
+    pub fn upscope(&mut self) -> &mut Self {
+        self.definitions.push("$upscope $end".to_string());
+        self
+    }
+    /// Declares a wire `width` bits wide, up to 64.
+    pub fn wire(&mut self, name: &str, width: usize) -> Signal {
+        assert!(
+            (1..=64).contains(&width),
+            "can't have a {width} bit wide wire"
+        );
+        let signal = Signal(self.widths.len());
+        self.definitions
+            .push(format!("$var wire {width} {} {name} $end", code(signal)));
+        self.widths.push(width);
+        self.values.push(None);
+        signal
+    }
+    /// Moves time forward to `time`. Changes go at the latest time set, or 0 before there is one.
+    pub fn time(&mut self, time: u64) -> &mut Self {
+        let now = self.time.unwrap_or(0);
+        assert!(time >= now, "can't go back from {now} to {time}");
+        if self.time != Some(time) {
+            writeln!(self.changes, "#{time}").unwrap();
+            self.time = Some(time);
+        }
+        self
+    }
+    /// Sets `signal` to `value`, leaving it out of the dump if that's what it already was.
+    pub fn change(&mut self, signal: Signal, value: u64) -> &mut Self {
+        if self.time.is_none() {
+            self.time(0);
+        }
+        if self.values[signal.0] == Some(value) {
+            return self;
+        }
+        self.values[signal.0] = Some(value);
+        match self.widths[signal.0] {
+            1 => writeln!(self.changes, "{}{}", value & 1, code(signal)),
+            width => writeln!(
+                self.changes,
+                "b{:0width$b} {}",
+                value & (u64::MAX >> (64 - width)),
+                code(signal)
+            ),
+        }
+        .unwrap();
+        self
+    }
+}
+
+impl Display for Vcd {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "$timescale {} $end", self.timescale)?;
+        for line in &self.definitions {
+            writeln!(f, "{line}")?;
+        }
+        writeln!(f, "$enddefinitions $end")?;
+        write!(f, "{}", self.changes)
+    }
+}
+
+/// Identifier codes are strings of the printable characters from `!` to `~`.
+fn code(signal: Signal) -> String {
+    let mut n = signal.0;
+    let mut out = String::new();
+    loop {
+        out.push((b'!' + (n % 94) as u8) as char);
+        n /= 94;
+        if n == 0 {
+            return out;
+        }
+        n -= 1;
+    }
+}
+
+#[test]
+fn test_vcd() {
+    let mut vcd = Vcd::new("1ns");
+    vcd.scope("top");
+    let clock = vcd.wire("clock", 1);
+    let count = vcd.wire("count", 3);
+    vcd.upscope();
+    vcd.change(clock, 0).change(count, 0);
+    vcd.time(1).change(clock, 1).change(count, 5);
+    vcd.time(2).change(clock, 1);
+    vcd.time(2).change(count, 6);
+    assert_eq!(
+        vcd.to_string(),
+        "$timescale 1ns $end
+$scope module top $end
+$var wire 1 ! clock $end
+$var wire 3 \" count $end
+$upscope $end
+$enddefinitions $end
+#0
+0!
+b000 \"
+#1
+1!
+b101 \"
+#2
+b110 \"
+"
+    );
+}
+
+#[test]
+fn test_code() {
+    assert_eq!(code(Signal(0)), "!");
+    assert_eq!(code(Signal(93)), "~");
+    assert_eq!(code(Signal(94)), "!!");
+    assert_eq!(code(Signal(95)), "\"!");
+    let codes = (0..20000)
+        .map(|n| code(Signal(n)))
+        .collect::<std::collections::HashSet<_>>();
+    assert_eq!(codes.len(), 20000);
+}